@@ -0,0 +1,47 @@
+#!/usr/bin/env python3
+# an example snake bot: heads for the food, without turning back on itself
+# (its heading is read from the snake line: head minus the block behind it)
+# (run it with: cargo run -- --bot snake="python3 bots/follow_food.py")
+import sys
+
+OPPOSITE = {"up": "down", "down": "up", "left": "right", "right": "left"}
+STEPS = {(0, -1): "up", (0, 1): "down", (-1, 0): "left", (1, 0): "right"}
+
+
+def blocks(words):
+    return [tuple(int(n) for n in word.split(",")) for word in words]
+
+
+def main():
+    role = "snake"
+    snakes, food = {}, None
+    for line in sys.stdin:
+        words = line.split()
+        if not words:
+            continue
+        if words[0] == "role":
+            role = words[1]
+        elif words[0] == "isready":
+            print("name follow-food", flush=True)
+            print("readyok", flush=True)
+        elif words[0] == "snake":
+            snakes[int(words[1])] = blocks(words[2:])
+        elif words[0] == "food":
+            food = None if words[1] == "none" else blocks(words[1:2])[0]
+        elif words[0] == "go":
+            mine = snakes.get(1 if role == "second-snake" else 0)
+            turn = ""
+            if mine and len(mine) > 1 and food:
+                (x, y), (nx, ny), (fx, fy) = mine[0], mine[1], food
+                heading = STEPS.get((x - nx, y - ny))
+                wanted = [d for d, ok in (("left", fx < x), ("right", fx > x), ("up", fy < y), ("down", fy > y)) if ok]
+                wanted = [d for d in wanted if heading is None or d != OPPOSITE[heading]]
+                if wanted:
+                    turn = " {}-{}".format(role, wanted[0])
+            print("play" + turn, flush=True)
+        elif words[0] == "quit":
+            break
+
+
+if __name__ == "__main__":
+    main()
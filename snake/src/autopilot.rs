@@ -0,0 +1,275 @@
+// computer-controlled snake
+// plans the shortest way to meet the moving food on its bouncing path (breadth-first search
+// over the board and the ticks to come), keeping clear of enemies, walls & its own tail.
+// when the food cannot be reached, or the way there would trap it, it heads for the most room
+// (it steers player one's snake, or any other on a board with several)
+use std::collections::VecDeque;
+
+use crate::controller::Controller;
+use crate::game::Game;
+use crate::input::Input;
+use crate::objects::{Direction, Snake};
+
+const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+pub struct Autopilot {
+    // snake it steers, player one first
+    player: usize
+}
+
+// what the planner knows about the board on this tick
+struct Board {
+    width: i32,
+    height: i32,
+
+    // for every block: the number of moves until it is free
+    // (0 = free, u32::MAX = enemy or wall, n = snake body leaving after n moves)
+    busy: Vec<u32>
+}
+
+// board design
+impl Board {
+    fn new(game: &Game, player: usize) -> Board {
+        let (width, height) = (game.width(), game.height());
+        let mut busy = vec![0; (width * height) as usize];
+
+        // boundaries
+        for x in 0..width {
+            for y in 0..height {
+                if x == 0 || y == 0 || x == width - 1 || y == height - 1 {
+                    busy[(y * width + x) as usize] = u32::MAX;
+                }
+            }
+        }
+
+        // enemies never leave
+        for block in game.enemy().blocks() {
+            busy[(block.y * width + block.x) as usize] = u32::MAX;
+        }
+
+        // the tail end of a snake moves away first
+        // (the very last block is already free for the next move, as in Snake::overlap_tail,
+        // but the other snake's stays in the way one move longer)
+        for (other, snake) in game.snakes().enumerate() {
+            let last = snake.body().count() as u32 - if other == player { 1 } else { 0 };
+            for (index, block) in snake.body().enumerate() {
+                busy[(block.y * width + block.x) as usize] = last - index as u32;
+            }
+        }
+
+        Board {
+            width,
+            height,
+            busy
+        }
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+        Some((y * self.width + x) as usize)
+    }
+
+    // true if the head can be on the block after some number of moves
+    fn open(&self, x: i32, y: i32, moves: u32) -> bool {
+        self.index(x, y).is_some_and(|index| self.busy[index] < moves)
+    }
+
+    // number of blocks reachable from a block, counting at most `limit`
+    fn room(&self, x: i32, y: i32, limit: usize) -> usize {
+        let mut seen = vec![false; self.busy.len()];
+        let mut queue = VecDeque::new();
+        if let Some(index) = self.index(x, y) {
+            seen[index] = true;
+            queue.push_back((x, y, 1));
+        }
+
+        let mut count = 0;
+        while let Some((x, y, moves)) = queue.pop_front() {
+            count += 1;
+            if count >= limit {
+                break;
+            }
+            for dir in DIRECTIONS.iter() {
+                let (next_x, next_y) = step(x, y, *dir);
+                if !self.open(next_x, next_y, moves + 1) {
+                    continue;
+                }
+                let index = self.index(next_x, next_y).unwrap();
+                if !seen[index] {
+                    seen[index] = true;
+                    queue.push_back((next_x, next_y, moves + 1));
+                }
+            }
+        }
+        count
+    }
+}
+
+// block next to a block in a direction
+fn step(x: i32, y: i32, dir: Direction) -> (i32, i32) {
+    match dir {
+        Direction::Up => (x, y - 1),
+        Direction::Down => (x, y + 1),
+        Direction::Left => (x - 1, y),
+        Direction::Right => (x + 1, y)
+    }
+}
+
+// autopilot design
+impl Autopilot {
+    pub fn new() -> Autopilot {
+        Autopilot::steering(0)
+    }
+
+    // an autopilot for another player's snake
+    pub fn steering(player: usize) -> Autopilot {
+        Autopilot { player }
+    }
+
+    // the snake it steers
+    fn snake<'a>(&self, game: &'a Game) -> &'a Snake {
+        game.snakes().nth(self.player).unwrap_or_else(|| game.snake())
+    }
+
+    // first move of the shortest way to meet the food, if there is one
+    // the snake's move on a tick is checked against the food before the food moves,
+    // so on tick n it has to reach the food's position after n - 1 food moves
+    fn chase(&self, game: &Game, board: &Board) -> Option<Direction> {
+        let food = game.food()?;
+        let horizon = 2 * (board.width + board.height) as usize;
+        let mut path = vec![food];
+        path.extend(game.food_path(food, game.food_speed(), horizon).into_iter().map(|(block, _)| block));
+
+        // states (x, y, direction, first move) the snake can be in after a tick
+        // -> a turn is taken on the next tick, so every tick is one move
+        let snake = self.snake(game);
+        let (head_x, head_y) = snake.head_position();
+        let mut layer = vec![(head_x, head_y, snake.head_direction(), None)];
+
+        for (ticks, target) in (0..).zip(path) {
+            // every (block, direction) is explored once per tick
+            let mut seen = vec![false; board.busy.len() * 4];
+            let mut next_layer = Vec::new();
+
+            for (x, y, dir, first) in layer {
+                for next_dir in DIRECTIONS.iter().copied() {
+                    // no turning back onto itself
+                    if next_dir == dir.opposite() {
+                        continue;
+                    }
+                    let first = first.or(Some(next_dir));
+
+                    let (next_x, next_y) = step(x, y, next_dir);
+                    if !board.open(next_x, next_y, ticks + 1) {
+                        continue;
+                    }
+                    if next_x == target.x && next_y == target.y {
+                        return first;
+                    }
+
+                    let index = board.index(next_x, next_y).unwrap() * 4 + next_dir as usize;
+                    if !seen[index] {
+                        seen[index] = true;
+                        next_layer.push((next_x, next_y, next_dir, first));
+                    }
+                }
+            }
+            if next_layer.is_empty() {
+                return None;
+            }
+            layer = next_layer;
+        }
+        None
+    }
+
+    // where the head ends up on the next tick after choosing a direction now,
+    // if the snake survives it
+    fn first_move(&self, board: &Board, head_x: i32, head_y: i32, dir: Direction) -> Option<(i32, i32)> {
+        let (x, y) = step(head_x, head_y, dir);
+        if !board.open(x, y, 1) {
+            return None;
+        }
+        Some((x, y))
+    }
+}
+
+impl Default for Autopilot {
+    fn default() -> Autopilot {
+        Autopilot::new()
+    }
+}
+
+impl Controller for Autopilot {
+    fn control(&mut self, game: &Game) -> Vec<Input> {
+        if game.game_over() || game.game_win() {
+            return Vec::new();
+        }
+
+        let board = Board::new(game, self.player);
+        let snake = self.snake(game);
+        let (head_x, head_y) = snake.head_position();
+        let current = snake.head_direction();
+        let length = snake.body().count();
+
+        // room left after a move, enough to hold the whole snake counts as safe
+        let room_after = |dir: Direction| {
+            match self.first_move(&board, head_x, head_y, dir) {
+                Some((x, y)) => board.room(x, y, length * 2),
+                None => 0
+            }
+        };
+
+        let chase = self.chase(game, &board).filter(|dir| room_after(*dir) >= length);
+
+        // fallback: the move with the most room, going straight on a tie
+        let dir = chase.unwrap_or_else(|| {
+            DIRECTIONS.iter()
+                .copied()
+                .filter(|dir| *dir != current.opposite())
+                .max_by_key(|dir| (room_after(*dir), *dir == current))
+                .unwrap_or(current)
+        });
+
+        // keys only matter for turns
+        if dir == current {
+            Vec::new()
+        } else if self.player == 0 {
+            vec![Input::Snake(dir)]
+        } else {
+            vec![Input::SecondSnake(dir)]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::Side;
+    use crate::paddle_ai::{Difficulty, PaddleAi};
+    use crate::settings::Settings;
+
+    #[test]
+    fn the_autopilot_eats_without_crashing() {
+        for seed in 0..5 {
+            let mut game = Game::new(&Settings::default(), seed);
+            let mut players: Vec<Box<dyn Controller>> = vec![
+                Box::new(Autopilot::new()),
+                Box::new(PaddleAi::new(Side::Left, Difficulty::Hard, seed)),
+                Box::new(PaddleAi::new(Side::Right, Difficulty::Hard, seed))
+            ];
+            let period = game.settings().speed.moving_period;
+            for _ in 0..300 {
+                for player in &mut players {
+                    for input in player.control(&game) {
+                        game.key_pressed(input);
+                    }
+                }
+                game.update(period + 0.01);
+                assert!(!game.game_over(), "seed {}: the snake crashed on tick {}", seed, game.tick());
+            }
+            assert!(game.score() > 0, "seed {}: nothing eaten", seed);
+        }
+    }
+}
@@ -0,0 +1,470 @@
+// bots: programs in any language playing a part of the game (the snake, the second snake or a
+// paddle), talking to it in lines of text over their stdin & stdout, in the spirit of UCI
+//
+// game -> bot, once at the start:
+//   protocol 1
+//   role left-paddle                 the part it plays
+//   board 30 30                      width & height
+//   isready                          the bot answers "readyok" (and may say "name <name>" before)
+// game -> bot, every tick of play:
+//   tick 120
+//   snake 0 5,5 5,6 5,7              every snake, player one first, head first
+//   paddle left 0,3 0,4 0,5          every paddle, first block first
+//   enemies 4,4 8,9
+//   food 12,7 1,-1                   where it is & where it goes each tick ("food none" if eaten)
+//   scores 3 1
+//   go 50                            the answer is due within 50 ms
+// bot -> game, once per "go":
+//   play left-paddle-hold-up         the inputs for the next tick, by name ("play" alone = none)
+// game -> bot, at the end:
+//   quit
+//
+// the game never waits on a bot longer than the time given: a late answer counts as "play"
+// (the snake goes on, the paddle does as it did) and is thrown away once it comes; a bot
+// missing too many ticks in a row, or gone, is left out and its part plays no inputs
+// (lines other than "play", e.g. "info ..." for the bot's own logs, are ignored; so are inputs
+// of other parts, and a "play" more than the "go"s asked for)
+//
+// in the window the game does not wait at all: bots are asked as soon as a tick is played, and
+// an answer counts if it comes before the next one (so a slow bot never holds up the frames);
+// headless games (tournaments) wait for the answer, up to the time given
+// what a bot writes on its stderr goes to bots.log in the data directory (e.g.
+// ~/.local/share/snake/bots.log), never to the screen
+//
+// the command is split into words as a shell would, with '...', "..." & \ to keep spaces in
+// a word, but it is run without a shell (no pipes, variables or globs)
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::controller::Controller;
+use crate::game::Game;
+use crate::input::Input;
+use crate::net::Role;
+use crate::objects::Block;
+
+// version of the protocol, told to bots first
+pub const PROTOCOL: u32 = 1;
+
+// time a bot has to answer every tick when none is given
+pub const DEFAULT_BUDGET: Duration = Duration::from_millis(50);
+
+// time a bot has to get ready
+const READY_TIMEOUT: Duration = Duration::from_secs(5);
+
+// ticks in a row a bot may miss before it is left out
+const MAX_MISSES: u32 = 20;
+
+// time a bot has to quit on its own before it is killed
+const QUIT_TIMEOUT: Duration = Duration::from_millis(200);
+
+pub struct Bot {
+    role: Role,
+    // what the bot calls itself (its command if it does not say)
+    name: String,
+    process: Child,
+    // lines to the bot's stdin & from its stdout, each over a thread of its own
+    // (so that a bot that stops reading or writing never holds up the game)
+    to_bot: Sender<String>,
+    from_bot: Receiver<String>,
+    budget: Duration,
+
+    // the last "go" is still waiting for its answer
+    asked: bool,
+    // answers still to come for ticks that went by without them
+    owed: usize,
+    // ticks missed in a row
+    misses: u32,
+    // why the bot was left out, if it was
+    trouble: Option<String>
+}
+
+// bot design
+impl Bot {
+    // start a bot, e.g. "python3 bot.py", for a part of a game, with a time to answer every tick
+    // -> an error if it cannot be started or does not get ready in time
+    pub fn spawn(command: &str, role: Role, game: &Game, budget: Duration) -> io::Result<Bot> {
+        let words = split_command(command)?;
+        let (program, args) = words.split_first()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no command to start the bot"))?;
+        let mut process = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(log())
+            .spawn()?;
+
+        let (to_bot, lines) = channel::<String>();
+        let mut stdin = process.stdin.take().unwrap();
+        thread::spawn(move || {
+            for line in lines {
+                if writeln!(stdin, "{}", line).and_then(|_| stdin.flush()).is_err() {
+                    break;
+                }
+            }
+        });
+        let (sender, from_bot) = channel();
+        let stdout = process.stdout.take().unwrap();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut bot = Bot {
+            role,
+            name: command.to_owned(),
+            process,
+            to_bot,
+            from_bot,
+            budget,
+            asked: false,
+            owed: 0,
+            misses: 0,
+            trouble: None
+        };
+        bot.handshake(game)?;
+        Ok(bot)
+    }
+
+    // the part it plays
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    // what the bot calls itself
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // why the bot was left out, if it was
+    pub fn trouble(&self) -> Option<&str> {
+        self.trouble.as_deref()
+    }
+
+    // tell the bot what it plays & wait for it to get ready
+    fn handshake(&mut self, game: &Game) -> io::Result<()> {
+        self.send(&format!("protocol {}", PROTOCOL));
+        self.send(&format!("role {}", self.role));
+        self.send(&format!("board {} {}", game.width(), game.height()));
+        self.send("isready");
+
+        let deadline = Instant::now() + READY_TIMEOUT;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            match self.from_bot.recv_timeout(left) {
+                Ok(line) if line.trim() == "readyok" => return Ok(()),
+                Ok(line) => {
+                    if let Some(name) = line.trim().strip_prefix("name ") {
+                        self.name = name.trim().to_owned();
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(io::Error::new(io::ErrorKind::TimedOut, "the bot did not get ready in time"));
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the bot quit before it was ready"));
+                }
+            }
+        }
+    }
+
+    fn send(&self, line: &str) {
+        // (a bot that is gone shows when its answer does not come)
+        let _ = self.to_bot.send(line.to_owned());
+    }
+
+    // leave the bot out for the rest of the game
+    fn give_up(&mut self, trouble: &str) {
+        self.trouble = Some(trouble.to_owned());
+        let _ = self.process.kill();
+    }
+
+    // tell the bot where everything is and ask for its inputs, without waiting for them
+    // (an answer still to come for the tick before is missed, and lines that came in since are
+    // read first, so that a late or extra answer is not taken for this one)
+    pub fn ask(&mut self, game: &Game) {
+        if self.trouble.is_some() {
+            return;
+        }
+        if self.asked {
+            self.miss();
+        }
+        while let Ok(line) = self.from_bot.try_recv() {
+            self.read(&line);
+        }
+        for line in describe(game) {
+            self.send(&line);
+        }
+        self.send(&format!("go {}", self.budget.as_millis()));
+        self.asked = true;
+    }
+
+    // the inputs answered to the last "go" if they came already, none (a missed tick) if not
+    pub fn answered(&mut self) -> Vec<Input> {
+        if !self.asked || self.trouble.is_some() {
+            return Vec::new();
+        }
+        loop {
+            match self.from_bot.try_recv() {
+                Ok(line) => {
+                    if let Some(inputs) = self.read(&line) {
+                        return inputs;
+                    }
+                }
+                Err(TryRecvError::Empty) => {
+                    self.miss();
+                    return Vec::new();
+                }
+                Err(TryRecvError::Disconnected) => {
+                    self.give_up("quit");
+                    return Vec::new();
+                }
+            }
+        }
+    }
+
+    // the inputs answered to the last "go", waiting for them up to the time given
+    fn wait(&mut self) -> Vec<Input> {
+        if !self.asked || self.trouble.is_some() {
+            return Vec::new();
+        }
+        let deadline = Instant::now() + self.budget;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            match self.from_bot.recv_timeout(left) {
+                Ok(line) => {
+                    if let Some(inputs) = self.read(&line) {
+                        return inputs;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    self.miss();
+                    return Vec::new();
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.give_up("quit");
+                    return Vec::new();
+                }
+            }
+        }
+    }
+
+    // the inputs of a line from the bot, if it answers the last "go"
+    fn read(&mut self, line: &str) -> Option<Vec<Input>> {
+        let mut words = line.split_whitespace();
+        if words.next() != Some("play") {
+            return None;
+        }
+        // (answers to ticks already gone by are thrown away, so are answers nobody asked for)
+        if self.owed > 0 {
+            self.owed -= 1;
+            return None;
+        }
+        if !self.asked {
+            return None;
+        }
+        self.asked = false;
+        self.misses = 0;
+        let role = self.role;
+        let inputs = words
+            .filter_map(|word| word.parse::<Input>().ok())
+            .filter(|input| role.owns(*input))
+            .collect();
+        Some(inputs)
+    }
+
+    // the last "go" went without an answer in time
+    fn miss(&mut self) {
+        self.asked = false;
+        self.owed += 1;
+        self.misses += 1;
+        if self.misses >= MAX_MISSES && self.trouble.is_none() {
+            self.give_up(&format!("missed {} ticks in a row", MAX_MISSES));
+        }
+    }
+}
+
+impl Controller for Bot {
+    // ask and wait for the answer: the caller is held up to the time given
+    fn control(&mut self, game: &Game) -> Vec<Input> {
+        self.ask(game);
+        self.wait()
+    }
+}
+
+impl Drop for Bot {
+    // ask the bot to quit, and make sure it does
+    fn drop(&mut self) {
+        self.send("quit");
+        let deadline = Instant::now() + QUIT_TIMEOUT;
+        while Instant::now() < deadline {
+            match self.process.try_wait() {
+                Ok(None) => thread::sleep(Duration::from_millis(10)),
+                _ => return
+            }
+        }
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+// the lines telling a bot where everything is on a tick
+pub fn describe(game: &Game) -> Vec<String> {
+    let view = game.view();
+    let mut lines = vec![format!("tick {}", game.tick())];
+    for (index, snake) in view.snakes.iter().enumerate() {
+        lines.push(format!("snake {}{}", index, blocks(snake)));
+    }
+    for (side, paddle) in &view.paddles {
+        lines.push(format!("paddle {}{}", format!("{:?}", side).to_lowercase(), blocks(paddle)));
+    }
+    lines.push(format!("enemies{}", blocks(&view.enemies)));
+    lines.push(match view.food {
+        Some(food) => {
+            let (speed_x, speed_y) = game.food_speed();
+            format!("food {},{} {},{}", food.x, food.y, speed_x, speed_y)
+        }
+        None => "food none".to_owned()
+    });
+    let scores: Vec<String> = view.scores.iter().map(|score| score.to_string()).collect();
+    lines.push(format!("scores {}", scores.join(" ")));
+    lines
+}
+
+// the words of a command as a shell splits them: on spaces, except inside '...' or "...",
+// and a \ keeps the next character as it is (outside '...')
+fn split_command(command: &str) -> io::Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some('"'), '\\') | (None, '\\') => {
+                if let Some(next) = chars.next() {
+                    word.get_or_insert_with(String::new).push(next);
+                }
+            }
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '\'') | (None, '"') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c)
+        }
+    }
+    if quote.is_some() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "a quote is not closed in the bot command"));
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+// where the bots' stderr goes: appended to bots.log in the data directory, or nowhere if
+// there is none
+fn log() -> Stdio {
+    let path = match dirs::data_dir() {
+        Some(dir) => dir.join("snake").join("bots.log"),
+        None => return Stdio::null()
+    };
+    match open_log(path) {
+        Ok(file) => Stdio::from(file),
+        Err(_) => Stdio::null()
+    }
+}
+
+fn open_log(path: PathBuf) -> io::Result<fs::File> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+// " x,y x,y ..."
+fn blocks(blocks: &[Block]) -> String {
+    blocks.iter().map(|block| format!(" {},{}", block.x, block.y)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::Direction;
+    use crate::settings::Settings;
+
+    // a bot written in sh: it gets ready, then runs `answer` on every "go" (with $n the number of
+    // the "go", from 1; no single quotes, the script is in some)
+    fn sh_bot(answer: &str, budget: u64) -> io::Result<Bot> {
+        let script = format!(
+            "read protocol; read role; read board; read isready; \
+             printf \"name sh-bot\\nreadyok\\n\"; \
+             n=0; while read line; do case \"$line\" in go*) n=$((n + 1)); {};; quit) exit;; esac; done",
+            answer
+        );
+        Bot::spawn(&format!("sh -c '{}'", script), Role::Snake, &game(), Duration::from_millis(budget))
+    }
+
+    fn game() -> Game {
+        Game::new(&Settings::default(), 1)
+    }
+
+    #[test]
+    fn commands_split_as_a_shell_would() {
+        let words = split_command(r#"python3 "my bots/bot.py" --name 'a b' x\ y"#).unwrap();
+        assert_eq!(words, vec!["python3", "my bots/bot.py", "--name", "a b", "x y"]);
+        assert_eq!(split_command("  ").unwrap(), Vec::<String>::new());
+        assert_eq!(split_command("''").unwrap(), vec![""]);
+        assert!(split_command("sh -c 'echo").is_err());
+    }
+
+    #[test]
+    fn a_bot_gets_ready_and_plays_its_own_part_only() {
+        let mut bot = sh_bot("printf \"info thinking\\nplay snake-down left-paddle-up bogus\\n\"", 1000).unwrap();
+        assert_eq!(bot.name(), "sh-bot");
+        assert_eq!(bot.control(&game()), vec![Input::Snake(Direction::Down)]);
+        assert_eq!(bot.control(&game()), vec![Input::Snake(Direction::Down)]);
+        assert!(bot.trouble().is_none());
+    }
+
+    #[test]
+    fn a_bot_that_quits_before_it_is_ready_does_not_start() {
+        let game = game();
+        let err = Bot::spawn("sh -c 'exit 0'", Role::Snake, &game, DEFAULT_BUDGET).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn a_late_answer_is_thrown_away_rather_than_taken_for_the_next_tick() {
+        // (the first answer comes long after its tick, the others right away)
+        let mut bot = sh_bot("if [ $n = 1 ]; then sleep 0.3; echo play snake-up; else echo play snake-down; fi", 100).unwrap();
+        assert_eq!(bot.control(&game()), Vec::new());
+        thread::sleep(Duration::from_millis(400));
+        assert_eq!(bot.control(&game()), vec![Input::Snake(Direction::Down)]);
+        assert!(bot.trouble().is_none());
+    }
+
+    #[test]
+    fn an_extra_answer_is_thrown_away_rather_than_taken_for_the_next_tick() {
+        let mut bot = sh_bot("echo play snake-down; echo play snake-up", 1000).unwrap();
+        assert_eq!(bot.control(&game()), vec![Input::Snake(Direction::Down)]);
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(bot.control(&game()), vec![Input::Snake(Direction::Down)]);
+    }
+
+    #[test]
+    fn a_bot_missing_too_many_ticks_is_left_out() {
+        let mut bot = sh_bot(":", 5).unwrap();
+        for _ in 0..MAX_MISSES {
+            assert_eq!(bot.control(&game()), Vec::new());
+        }
+        assert_eq!(bot.trouble(), Some("missed 20 ticks in a row"));
+    }
+}
@@ -0,0 +1,527 @@
+// command line options
+use std::env;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use snake::bot::DEFAULT_BUDGET;
+use snake::link::Conditions;
+use snake::net::{Role, DEFAULT_PORT};
+use snake::tournament::{Format, Kind};
+use snake::{Agent, Difficulty, Side};
+
+pub const USAGE: &str = "usage: snake [--config FILE] [--keys FILE] [--seed N] [--record FILE] [--replay FILE]
+             [--paddle-ai [SIDE:]LEVEL]... [--autopilot] [--tui] [--gif FILE]
+             [--load FILE] [--quick-save FILE] [--bot ROLE=COMMAND]... [--bot-time MS]
+             [--host ADDRESS [--rollback [--netsim LATENCY,JITTER,LOSS]]]
+       snake --join ADDRESS --role ROLE [--rollback [--netsim LATENCY,JITTER,LOSS]]
+             [--config FILE] [--keys FILE] [--tui] [--gif FILE]
+       snake --watch ADDRESS [--config FILE] [--tui] [--gif FILE]
+       snake export REPLAY OUT.png|OUT.gif [--tick N] [--block PIXELS]
+       snake tournament [--snake NAME=COMMAND]... [--paddle NAME=COMMAND]...
+             [--format round-robin|swiss:ROUNDS] [--games N] [--seed N] [--jobs N]
+             [--max-ticks N] [--bot-time MS] [--config FILE] [--out DIR]";
+
+// pixels per block of exported images & animations
+const EXPORT_BLOCK: u32 = 12;
+pub const GIF_BLOCK: u32 = EXPORT_BLOCK;
+
+// settings file read when --config is not given (skipped if missing)
+const DEFAULT_CONFIG: &str = "snake.toml";
+
+// seeds every pairing of a tournament plays, and the ticks a game has before it is a draw
+const TOURNAMENT_GAMES: u32 = 5;
+const TOURNAMENT_MAX_TICKS: u64 = 5000;
+
+// what the command line asks for
+pub enum Command {
+    // play a match (or watch a replay)
+    // (boxed, it is much bigger than the other commands)
+    Play(Box<Options>),
+    // draw a replay into an image or an animation
+    Export(Export),
+    // play agents against each other without any window
+    Tournament(TournamentOptions)
+}
+
+pub struct Options {
+    // settings file to load, if any
+    pub config: Option<PathBuf>,
+
+    // key bindings file to load & save (the one in the config directory if not given)
+    pub keys: Option<PathBuf>,
+
+    // seed for every random decision in the game
+    pub seed: u64,
+
+    // save every input into this replay file on exit
+    pub record: Option<PathBuf>,
+
+    // play this replay file back instead of a live match
+    pub replay: Option<PathBuf>,
+
+    // play on from this saved game instead of starting a new match
+    pub load: Option<PathBuf>,
+
+    // file the quick-save & quick-load keys use (the one in the data directory if not given)
+    // (.json for JSON, anything else for the compact binary format)
+    pub quick_save: Option<PathBuf>,
+
+    // paddles the computer plays, with their difficulty
+    // (no side = every paddle)
+    pub paddle_ai: Vec<(Option<Side>, Difficulty)>,
+
+    // the computer steers the snake
+    pub autopilot: bool,
+
+    // programs playing parts of the game, with the command starting each
+    // (e.g. left-paddle & "python3 bot.py"), and the time they have to answer every tick
+    pub bots: Vec<(Role, String)>,
+    pub bot_time: Duration,
+
+    // play in the terminal instead of a window
+    pub tui: bool,
+
+    // save the match as an animated GIF on exit
+    pub gif: Option<PathBuf>,
+
+    // let other players join the match on this address
+    pub host: Option<String>,
+
+    // play a part of the match hosted on this address instead
+    pub join: Option<(String, Role)>,
+
+    // only watch the match hosted on this address
+    pub watch: Option<String>,
+
+    // play the hosted or joined match with rollback over UDP, each peer running the game
+    // (instead of the host running it alone over TCP)
+    pub rollback: bool,
+
+    // send the rollback packets through a simulated network this bad
+    pub netsim: Option<Conditions>
+}
+
+pub struct Export {
+    pub replay: PathBuf,
+
+    // .png for one frame, .gif for the whole replay
+    pub out: PathBuf,
+
+    // tick of the frame a PNG shows (the last one if not given)
+    pub tick: Option<u64>,
+
+    // pixels per block
+    pub block: u32
+}
+
+pub struct TournamentOptions {
+    // settings file the games are played with, if any
+    pub config: Option<PathBuf>,
+
+    // snake & paddle agents, in the order given
+    pub agents: Vec<Agent>,
+
+    pub format: Format,
+
+    // seeds every pairing plays, from this one on
+    pub games: u32,
+    pub seed: u64,
+
+    // games played at once (one per core if not given)
+    pub jobs: usize,
+
+    // ticks a game has before it is a draw
+    pub max_ticks: u64,
+
+    // time bots have to answer every tick
+    pub bot_time: Duration,
+
+    // directory the standings, the games & their replays are written to
+    pub out: PathBuf
+}
+
+impl Command {
+    // read the command from the command line
+    pub fn parse() -> Result<Command, String> {
+        let mut args = env::args().skip(1).peekable();
+        if args.peek().map(String::as_str) == Some("export") {
+            args.next();
+            return Ok(Command::Export(Export::parse(args)?));
+        }
+        if args.peek().map(String::as_str) == Some("tournament") {
+            args.next();
+            return Ok(Command::Tournament(TournamentOptions::parse(args)?));
+        }
+        Ok(Command::Play(Box::new(Options::parse(args)?)))
+    }
+}
+
+impl Options {
+    // read the options of a match
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+        let mut config = None;
+        let mut keys = None;
+        let mut seed = None;
+        let mut record = None;
+        let mut replay = None;
+        let mut load = None;
+        let mut quick_save = None;
+        let mut paddle_ai = Vec::new();
+        let mut autopilot = false;
+        let mut bots: Vec<(Role, String)> = Vec::new();
+        let mut bot_time = DEFAULT_BUDGET;
+        let mut tui = false;
+        let mut gif = None;
+        let mut host = None;
+        let mut join = None;
+        let mut role = None;
+        let mut watch = None;
+        let mut rollback = false;
+        let mut netsim = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config" => {
+                    config = Some(PathBuf::from(args.next().ok_or("--config needs a file")?));
+                }
+                "--keys" => {
+                    keys = Some(PathBuf::from(args.next().ok_or("--keys needs a file")?));
+                }
+                "--seed" => {
+                    let value = args.next().ok_or("--seed needs a number")?;
+                    let value = value.parse::<u64>()
+                        .map_err(|_| format!("invalid seed '{}'", value))?;
+                    seed = Some(value);
+                }
+                "--record" => {
+                    record = Some(PathBuf::from(args.next().ok_or("--record needs a file")?));
+                }
+                "--replay" => {
+                    replay = Some(PathBuf::from(args.next().ok_or("--replay needs a file")?));
+                }
+                "--load" => {
+                    load = Some(PathBuf::from(args.next().ok_or("--load needs a saved game")?));
+                }
+                "--quick-save" => {
+                    quick_save = Some(PathBuf::from(args.next().ok_or("--quick-save needs a file")?));
+                }
+                "--paddle-ai" => {
+                    let value = args.next().ok_or("--paddle-ai needs a difficulty")?;
+                    paddle_ai.push(parse_paddle_ai(&value)?);
+                }
+                "--autopilot" => autopilot = true,
+                "--bot" => {
+                    let value = args.next().ok_or("--bot needs a part & a command, e.g. snake=\"python3 bot.py\"")?;
+                    let bot = parse_bot(&value)?;
+                    if bots.iter().any(|(role, _)| *role == bot.0) {
+                        return Err(format!("two bots for the {}", bot.0));
+                    }
+                    bots.push(bot);
+                }
+                "--bot-time" => {
+                    let value = args.next().ok_or("--bot-time needs a number of milliseconds")?;
+                    bot_time = match value.parse::<u64>() {
+                        Ok(ms) if (1..=10_000).contains(&ms) => Duration::from_millis(ms),
+                        _ => return Err(format!("invalid bot time '{}' (1 to 10000 ms)", value))
+                    };
+                }
+                "--tui" => tui = true,
+                "--gif" => {
+                    gif = Some(PathBuf::from(args.next().ok_or("--gif needs a file")?));
+                }
+                "--host" => {
+                    host = Some(with_port(&args.next().ok_or("--host needs an address, e.g. 0.0.0.0:7777")?));
+                }
+                "--join" => {
+                    join = Some(with_port(&args.next().ok_or("--join needs the address of the host")?));
+                }
+                "--watch" => {
+                    watch = Some(with_port(&args.next().ok_or("--watch needs the address of the host")?));
+                }
+                "--role" => {
+                    let value = args.next().ok_or("--role needs a part to play, e.g. left-paddle")?;
+                    role = Some(value.parse::<Role>()?);
+                }
+                "--rollback" => rollback = true,
+                "--netsim" => {
+                    let value = args.next().ok_or("--netsim needs latency,jitter,loss, e.g. 80,20,5")?;
+                    netsim = Some(value.parse::<Conditions>()?);
+                }
+                _ => return Err(format!("unknown option '{}'", arg))
+            }
+        }
+
+        // a replay brings its own seed & inputs
+        if replay.is_some() && (seed.is_some() || record.is_some()) {
+            return Err("--replay cannot be combined with --seed or --record".to_owned());
+        }
+
+        // a saved game brings its own seed, and its ticks do not start where a replay's do
+        if load.is_some() && (replay.is_some() || seed.is_some() || record.is_some()) {
+            return Err("--load cannot be combined with --replay, --seed or --record".to_owned());
+        }
+
+        // a joined match belongs to its host: its seed, its computer players & its recording
+        let join = match (join, role) {
+            (Some(address), Some(role)) => Some((address, role)),
+            (Some(_), None) => return Err("--join needs a --role".to_owned()),
+            (None, Some(_)) => return Err("--role only goes with --join".to_owned()),
+            (None, None) => None
+        };
+        if join.is_some() && (host.is_some() || replay.is_some() || seed.is_some() || record.is_some() || load.is_some()) {
+            return Err("--join cannot be combined with --host, --replay, --seed, --record or --load".to_owned());
+        }
+        if join.is_some() && (autopilot || !paddle_ai.is_empty()) {
+            return Err("--join cannot be combined with --autopilot or --paddle-ai".to_owned());
+        }
+        // so does a watched one, and spectators have no part to play
+        let watching = watch.is_some();
+        if watching && (join.is_some() || host.is_some() || replay.is_some() || seed.is_some() || record.is_some() || load.is_some()) {
+            return Err("--watch cannot be combined with --join, --host, --replay, --seed, --record or --load".to_owned());
+        }
+        if watching && (autopilot || !paddle_ai.is_empty() || rollback) {
+            return Err("--watch cannot be combined with --autopilot, --paddle-ai or --rollback".to_owned());
+        }
+        // bots play the parts of a match run here
+        if !bots.is_empty() && (join.is_some() || watching || replay.is_some() || rollback) {
+            return Err("--bot cannot be combined with --join, --watch, --replay or --rollback".to_owned());
+        }
+        if autopilot && bots.iter().any(|(role, _)| *role == Role::Snake) {
+            return Err("--autopilot and a snake bot cannot both steer the snake".to_owned());
+        }
+        if host.is_some() && replay.is_some() {
+            return Err("--host cannot be combined with --replay".to_owned());
+        }
+
+        // both peers of a rollback match play it from their two players' inputs alone
+        // (no computer players, and no recording of ticks that may be played again)
+        if rollback && host.is_none() && join.is_none() {
+            return Err("--rollback only goes with --host or --join".to_owned());
+        }
+        if rollback && (record.is_some() || load.is_some() || autopilot || !paddle_ai.is_empty()) {
+            return Err("--rollback cannot be combined with --record, --load, --autopilot or --paddle-ai".to_owned());
+        }
+        if netsim.is_some() && !rollback {
+            return Err("--netsim only goes with --rollback".to_owned());
+        }
+
+        // fall back on the settings file next to the game, if there is one
+        if config.is_none() && Path::new(DEFAULT_CONFIG).exists() {
+            config = Some(PathBuf::from(DEFAULT_CONFIG));
+        }
+
+        Ok(Options {
+            config,
+            keys,
+            // without a seed, pick one from the clock
+            seed: seed.unwrap_or_else(clock_seed),
+            record,
+            replay,
+            load,
+            quick_save,
+            paddle_ai,
+            autopilot,
+            bots,
+            bot_time,
+            tui,
+            gif,
+            host,
+            join,
+            watch,
+            rollback,
+            netsim
+        })
+    }
+}
+
+impl Export {
+    // read the options of an export
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Export, String> {
+        let mut files = Vec::new();
+        let mut tick = None;
+        let mut block = EXPORT_BLOCK;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--tick" => {
+                    let value = args.next().ok_or("--tick needs a number")?;
+                    tick = Some(value.parse::<u64>().map_err(|_| format!("invalid tick '{}'", value))?);
+                }
+                "--block" => {
+                    let value = args.next().ok_or("--block needs a number of pixels")?;
+                    block = match value.parse::<u32>() {
+                        Ok(pixels) if (1..=100).contains(&pixels) => pixels,
+                        _ => return Err(format!("invalid block size '{}' (1 to 100 pixels)", value))
+                    };
+                }
+                _ if arg.starts_with("--") => return Err(format!("unknown option '{}'", arg)),
+                _ => files.push(PathBuf::from(arg))
+            }
+        }
+
+        let (replay, out) = match files.as_slice() {
+            [replay, out] => (replay.clone(), out.clone()),
+            _ => return Err("export needs a replay file and an output file".to_owned())
+        };
+        let extension = out.extension().and_then(|extension| extension.to_str()).unwrap_or("");
+        if extension != "png" && extension != "gif" {
+            return Err(format!("cannot export to {} (.png or .gif)", out.display()));
+        }
+        if extension == "gif" && tick.is_some() {
+            return Err("--tick only picks the frame of a .png".to_owned());
+        }
+
+        Ok(Export {
+            replay,
+            out,
+            tick,
+            block
+        })
+    }
+}
+
+impl TournamentOptions {
+    // read the options of a tournament
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<TournamentOptions, String> {
+        let mut config = None;
+        let mut agents = Vec::new();
+        let mut format = Format::RoundRobin;
+        let mut games = TOURNAMENT_GAMES;
+        let mut seed = 0;
+        let mut jobs = None;
+        let mut max_ticks = TOURNAMENT_MAX_TICKS;
+        let mut bot_time = DEFAULT_BUDGET;
+        let mut out = PathBuf::from("tournament");
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config" => {
+                    config = Some(PathBuf::from(args.next().ok_or("--config needs a file")?));
+                }
+                "--snake" => {
+                    let value = args.next().ok_or("--snake needs a name & a command, e.g. v2=\"python3 bot.py\"")?;
+                    agents.push(Agent::parse(Kind::Snake, &value)?);
+                }
+                "--paddle" => {
+                    let value = args.next().ok_or("--paddle needs a name & a command, e.g. hard=builtin:hard")?;
+                    agents.push(Agent::parse(Kind::Paddle, &value)?);
+                }
+                "--format" => {
+                    format = args.next().ok_or("--format needs round-robin or swiss:ROUNDS")?.parse()?;
+                }
+                "--games" => {
+                    let value = args.next().ok_or("--games needs a number")?;
+                    games = match value.parse::<u32>() {
+                        Ok(games) if games > 0 => games,
+                        _ => return Err(format!("invalid number of games '{}'", value))
+                    };
+                }
+                "--seed" => {
+                    let value = args.next().ok_or("--seed needs a number")?;
+                    seed = value.parse::<u64>().map_err(|_| format!("invalid seed '{}'", value))?;
+                }
+                "--jobs" => {
+                    let value = args.next().ok_or("--jobs needs a number")?;
+                    jobs = match value.parse::<usize>() {
+                        Ok(jobs) if jobs > 0 => Some(jobs),
+                        _ => return Err(format!("invalid number of jobs '{}'", value))
+                    };
+                }
+                "--max-ticks" => {
+                    let value = args.next().ok_or("--max-ticks needs a number")?;
+                    max_ticks = match value.parse::<u64>() {
+                        Ok(ticks) if ticks > 0 => ticks,
+                        _ => return Err(format!("invalid number of ticks '{}'", value))
+                    };
+                }
+                "--bot-time" => {
+                    let value = args.next().ok_or("--bot-time needs a number of milliseconds")?;
+                    bot_time = match value.parse::<u64>() {
+                        Ok(ms) if (1..=10_000).contains(&ms) => Duration::from_millis(ms),
+                        _ => return Err(format!("invalid bot time '{}' (1 to 10000 ms)", value))
+                    };
+                }
+                "--out" => {
+                    out = PathBuf::from(args.next().ok_or("--out needs a directory")?);
+                }
+                _ => return Err(format!("unknown option '{}'", arg))
+            }
+        }
+
+        if config.is_none() && Path::new(DEFAULT_CONFIG).exists() {
+            config = Some(PathBuf::from(DEFAULT_CONFIG));
+        }
+        let jobs = jobs.unwrap_or_else(|| thread::available_parallelism().map_or(1, |jobs| jobs.get()));
+
+        Ok(TournamentOptions {
+            config,
+            agents,
+            format,
+            games,
+            seed,
+            jobs,
+            max_ticks,
+            bot_time,
+            out
+        })
+    }
+}
+
+// "hard" or "left:hard"
+fn parse_paddle_ai(value: &str) -> Result<(Option<Side>, Difficulty), String> {
+    match value.split_once(':') {
+        Some((side, difficulty)) => Ok((Some(side.parse()?), difficulty.parse()?)),
+        None => Ok((None, value.parse()?))
+    }
+}
+
+// "left-paddle=./bot" or "snake=python3 bot.py"
+fn parse_bot(value: &str) -> Result<(Role, String), String> {
+    match value.split_once('=') {
+        Some((role, command)) if !command.trim().is_empty() => Ok((role.parse()?, command.trim().to_owned())),
+        _ => Err(format!("invalid bot '{}' (ROLE=COMMAND, e.g. snake=\"python3 bot.py\")", value))
+    }
+}
+
+// "192.168.1.20" -> "192.168.1.20:7777", "::1" or "[::1]" -> "[::1]:7777",
+// addresses with a port stay as they are
+fn with_port(address: &str) -> String {
+    if address.parse::<SocketAddr>().is_ok() {
+        return address.to_owned();
+    }
+    if let Ok(ip) = address.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+        return SocketAddr::new(ip, DEFAULT_PORT).to_string();
+    }
+    // a host name, e.g. "localhost" or "localhost:7000"
+    if address.contains(':') {
+        address.to_owned()
+    } else {
+        format!("{}:{}", address, DEFAULT_PORT)
+    }
+}
+
+// seed taken from the current time
+fn clock_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_default_port_is_added_when_there_is_none() {
+        assert_eq!(with_port("192.168.1.20"), "192.168.1.20:7777");
+        assert_eq!(with_port("192.168.1.20:9000"), "192.168.1.20:9000");
+        assert_eq!(with_port("::1"), "[::1]:7777");
+        assert_eq!(with_port("[::1]"), "[::1]:7777");
+        assert_eq!(with_port("[::1]:9000"), "[::1]:9000");
+        assert_eq!(with_port("localhost"), "localhost:7777");
+        assert_eq!(with_port("localhost:9000"), "localhost:9000");
+    }
+}
@@ -0,0 +1,65 @@
+// tournaments run from the command line: every game is printed & its replay saved as it ends,
+// then the standings & the list of games are written next to the replays
+use std::fs;
+use std::io;
+
+use snake::tournament::{replay_name, Outcome};
+use snake::{Settings, Tournament, TournamentSettings};
+
+use crate::cli::TournamentOptions;
+
+// play the tournament and write out its results
+pub fn run(options: &TournamentOptions) -> Result<(), String> {
+    let game = match &options.config {
+        Some(path) => Settings::load(path).map_err(|err| err.to_string())?,
+        None => Settings::default()
+    };
+    let settings = TournamentSettings {
+        game,
+        format: options.format,
+        games: options.games,
+        seed: options.seed,
+        jobs: options.jobs,
+        max_ticks: options.max_ticks,
+        bot_time: options.bot_time
+    };
+    let mut tournament = Tournament::new(options.agents.clone(), settings)?;
+
+    let replays = options.out.join("replays");
+    fs::create_dir_all(&replays).map_err(|err| format!("cannot create {}: {}", replays.display(), err))?;
+
+    let agents = &options.agents;
+    let mut failed: Option<io::Error> = None;
+    tournament.run(|result| {
+        let pairing = &result.pairing;
+        let (first, second) = (&agents[pairing.first].name, &agents[pairing.second].name);
+        let winner = match result.outcome {
+            Outcome::First => first.as_str(),
+            Outcome::Second => second.as_str(),
+            Outcome::Draw => "draw"
+        };
+        eprintln!(
+            "round {} game {}: {} vs {} (seed {}) -> {} after {} ticks",
+            pairing.round, pairing.number + 1, first, second, pairing.seed, winner, result.ticks
+        );
+        for trouble in &result.trouble {
+            eprintln!("  {}", trouble);
+        }
+        if let Err(err) = result.replay.save(replays.join(replay_name(agents, result))) {
+            failed.get_or_insert(err);
+        }
+    })?;
+    if let Some(err) = failed {
+        return Err(format!("cannot save replays in {}: {}", replays.display(), err));
+    }
+
+    let standings = tournament.standings_text();
+    let files = [("standings.txt", standings.clone()), ("games.csv", tournament.games_csv())];
+    for (name, text) in files.iter() {
+        let path = options.out.join(name);
+        fs::write(&path, text).map_err(|err| format!("cannot write {}: {}", path.display(), err))?;
+    }
+    print!("{}", standings);
+    eprintln!("standings, games & replays saved in {}", options.out.display());
+    Ok(())
+}
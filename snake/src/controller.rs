@@ -0,0 +1,8 @@
+use crate::game::Game;
+use crate::input::Input;
+
+// plays (part of) the game instead of a person
+// it looks at the game once per tick and answers with the inputs to give before the next one
+pub trait Controller {
+    fn control(&mut self, game: &Game) -> Vec<Input>;
+}
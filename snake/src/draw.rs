@@ -1,6 +1,9 @@
 use piston_window::{rectangle, Context, G2d};
 use piston_window::types::Color;
 
+use snake::{Motion, View};
+use snake::settings::Colors;
+
 const BLOCK_SIZE: f64 = 25.0;       // block -  pixels
 
 // cast width & height of the gameboard into float type and multiply it by block size
@@ -21,6 +24,16 @@ pub fn draw_block(color: Color, x: i32, y: i32, con: &Context, g: &mut G2d) {
     rectangle(
         color, 
         [gui_x, gui_y, BLOCK_SIZE, BLOCK_SIZE], 
+        con.transform,
+        g,
+    );
+}
+
+// drawing blocks in between two cells (for motion between ticks)
+pub fn draw_block_at(color: Color, x: f64, y: f64, con: &Context, g: &mut G2d) {
+    rectangle(
+        color,
+        [x * BLOCK_SIZE, y * BLOCK_SIZE, BLOCK_SIZE, BLOCK_SIZE],
         con.transform, 
         g,
     );
@@ -47,3 +60,47 @@ pub fn draw_rectangle(
         g,
     );
 }
+
+// draw every game component
+// (the snakes & the food in between their last two ticks)
+pub fn draw_game(view: &View, motion: &Motion, colors: &Colors, con: &Context, g: &mut G2d) {
+    // snakes
+    for index in 0..view.snakes.len() {
+        for (x, y) in motion.snake(view, index) {
+            draw_block_at(colors.snake_color(index), x, y, con, g);
+        }
+    }
+
+    // paddles
+    for (_, body) in &view.paddles {
+        for block in body {
+            draw_block(colors.paddle, block.x, block.y, con, g);
+        }
+    }
+
+    // enemy
+    for block in &view.enemies {
+        draw_block(colors.enemy, block.x, block.y, con, g);
+    }
+
+    // food
+    if let Some((x, y)) = motion.food(view) {
+        draw_block_at(colors.food, x, y, con, g);
+    }
+
+    // boundaries
+    draw_rectangle(colors.border, 0, 0, view.width, 1, con, g);
+    draw_rectangle(colors.border, 0, view.height - 1, view.width, 1, con, g);
+    draw_rectangle(colors.side, 0, 0, 1, view.height, con, g);
+    draw_rectangle(colors.side, view.width - 1, 0, 1, view.height, con, g);
+
+    // game over screen
+    if view.game_over {
+        draw_rectangle(colors.game_over, 0, 0, view.width, view.height, con, g);
+    }
+
+    // game win screen
+    if view.game_win {
+        draw_rectangle(colors.game_win, 0, 0, view.width, view.height, con, g);
+    }
+}
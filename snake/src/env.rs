@@ -0,0 +1,465 @@
+// gym-style environment for training agents on the game, without any window
+// reset(seed) starts a match, step(action) gives the agent's inputs and plays one tick,
+// answering with what the agent sees, its reward, whether the match is over & some info
+//
+// the agent plays whatever the computer does not: paddles listed in `paddle_ai`
+// and the snake with `autopilot` are played by the built-in controllers
+// (one snake only: the rewards are the snake's, so a two-snake game is refused)
+use crate::autopilot::Autopilot;
+use crate::controller::Controller;
+use crate::game::Game;
+use crate::input::Input;
+use crate::objects::{Block, Direction, Side};
+use crate::paddle_ai::{Difficulty, PaddleAi};
+use crate::settings::{Settings, SettingsError};
+
+// order of the sides in the feature vector
+const SIDES: [Side; 4] = [Side::Left, Side::Right, Side::Top, Side::Bottom];
+const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+// how the board is handed to the agent
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Encoding {
+    // one height x width plane per kind of block, 1.0 where there is one
+    // (in the order of Encoding::PLANES)
+    Grid,
+    // a short list of numbers about the snake, the food & the paddles
+    // (in the order of Encoding::FEATURES)
+    Features
+}
+
+// reward for each thing that can happen on a tick
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rewards {
+    // the snake eats the food
+    pub eat: f64,
+    // the snake reaches the win score
+    pub win: f64,
+    // the snake runs into a wall, an enemy or itself
+    pub crash: f64,
+    // a paddle lets the food through
+    pub miss: f64,
+    // every tick the match goes on
+    pub tick: f64
+}
+
+#[derive(Debug, Clone)]
+pub struct EnvSettings {
+    // board, speeds, paddles & win score of the game
+    pub game: Settings,
+    pub encoding: Encoding,
+    pub rewards: Rewards,
+
+    // paddles the computer plays, with their difficulty
+    pub paddle_ai: Vec<(Side, Difficulty)>,
+    // the computer steers the snake
+    pub autopilot: bool,
+
+    // the match is cut short after this many ticks, if any
+    pub max_ticks: Option<u64>
+}
+
+// what the agent does before a tick: the inputs it gives
+// (no input = the snake goes on in its direction & the paddles stay)
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Action(pub Vec<Input>);
+
+// what the agent sees: numbers laid out as `shape` (planes, rows, columns for the grid)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observation {
+    pub shape: Vec<usize>,
+    pub data: Vec<f32>
+}
+
+// what happened on a step, besides the reward
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Info {
+    pub tick: u64,
+    pub score: i32,
+    pub ate: bool,
+    pub win: bool,
+    pub crash: bool,
+    // side whose paddle missed the food
+    pub missed_by: Option<Side>,
+    // the match reached max_ticks
+    pub truncated: bool
+}
+
+pub struct Env {
+    settings: EnvSettings,
+    game: Game,
+    controllers: Vec<Box<dyn Controller>>,
+    // ticks played since the last reset
+    ticks: u64
+}
+
+// encoding design
+impl Encoding {
+    pub const PLANES: [&'static str; 6] = ["snake", "snake-head", "enemies", "food", "food-next", "paddles"];
+
+    pub const FEATURES: [&'static str; 28] = [
+        "head-x", "head-y",
+        "heading-up", "heading-down", "heading-left", "heading-right",
+        "danger-up", "danger-down", "danger-left", "danger-right",
+        "food", "food-dx", "food-dy", "food-speed-x", "food-speed-y",
+        "left-paddle", "left-paddle-center", "left-paddle-length",
+        "right-paddle", "right-paddle-center", "right-paddle-length",
+        "top-paddle", "top-paddle-center", "top-paddle-length",
+        "bottom-paddle", "bottom-paddle-center", "bottom-paddle-length",
+        "score"
+    ];
+}
+
+// the snake is rewarded for eating & winning, punished for crashing
+impl Default for Rewards {
+    fn default() -> Rewards {
+        Rewards {
+            eat: 1.0,
+            win: 5.0,
+            crash: -1.0,
+            miss: 0.0,
+            tick: 0.0
+        }
+    }
+}
+
+// the agent plays the snake against hard computer paddles
+impl Default for EnvSettings {
+    fn default() -> EnvSettings {
+        let game = Settings::default();
+        let paddle_ai = game.paddles.iter()
+            .map(|paddle| (paddle.side, Difficulty::Hard))
+            .collect();
+        EnvSettings {
+            game,
+            encoding: Encoding::Grid,
+            rewards: Rewards::default(),
+            paddle_ai,
+            autopilot: false,
+            max_ticks: None
+        }
+    }
+}
+
+// action design
+impl Action {
+    // number of actions in a discrete action space: nothing, then one per input
+    pub fn count() -> usize {
+        Action::inputs().count() + 1
+    }
+
+    // action of a discrete action space
+    // 0 = nothing, then every input in the order of their names
+    pub fn from_index(index: usize) -> Option<Action> {
+        if index == 0 {
+            return Some(Action::default());
+        }
+        Action::inputs().nth(index - 1).map(Action::from)
+    }
+
+    // inputs an agent plays with: snake turns & paddle moves
+    // (starting a new match is up to reset, held keys need a clock and there is no second snake)
+    fn inputs() -> impl Iterator<Item = Input> {
+        Input::all().filter(|input| matches!(input, Input::Snake(_) | Input::PaddleUp(_) | Input::PaddleDown(_)))
+    }
+}
+
+impl From<Input> for Action {
+    fn from(input: Input) -> Action {
+        Action(vec![input])
+    }
+}
+
+// environment design
+impl Env {
+    // an environment ready to play, as if reset with seed 0
+    // -> refused if the game settings are out of range or have two snakes
+    pub fn new(settings: EnvSettings) -> Result<Env, SettingsError> {
+        settings.game.validate()?;
+        if settings.game.rules.snakes != 1 {
+            return Err(SettingsError::Invalid(format!(
+                "the environment plays one snake (got rules.snakes = {})",
+                settings.game.rules.snakes
+            )));
+        }
+        let game = Game::new(&settings.game, 0);
+        let mut env = Env {
+            settings,
+            game,
+            controllers: Vec::new(),
+            ticks: 0
+        };
+        env.set_up_controllers(0);
+        Ok(env)
+    }
+
+    // start a new match from the seed
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.game = Game::new(&self.settings.game, seed);
+        self.ticks = 0;
+        self.set_up_controllers(seed);
+        self.observe()
+    }
+
+    // the computer players of a match
+    // (they get seeds of their own from the match's, as in the window)
+    fn set_up_controllers(&mut self, seed: u64) {
+        self.controllers.clear();
+        if self.settings.autopilot {
+            self.controllers.push(Box::new(Autopilot::new()));
+        }
+        for (index, paddle) in self.settings.game.paddles.iter().enumerate() {
+            let difficulty = self.settings.paddle_ai.iter()
+                .find(|(side, _)| *side == paddle.side)
+                .map(|(_, difficulty)| *difficulty);
+            if let Some(difficulty) = difficulty {
+                let ai_seed = seed.wrapping_add(index as u64 + 1);
+                self.controllers.push(Box::new(PaddleAi::new(paddle.side, difficulty, ai_seed)));
+            }
+        }
+    }
+
+    // give the agent's inputs, let the computer players answer & play one tick
+    // (everyone sees the board as the agent did, as the window asks its computer players
+    // right before every tick)
+    // once the match is over, steps change nothing until the next reset
+    pub fn step(&mut self, action: Action) -> (Observation, f64, bool, Info) {
+        if self.done() {
+            return (self.observe(), 0.0, true, self.info(false));
+        }
+
+        let score = self.game.score();
+        for input in action.0 {
+            self.game.key_pressed(input);
+        }
+        for controller in &mut self.controllers {
+            for input in controller.control(&self.game) {
+                self.game.key_pressed(input);
+            }
+        }
+        self.game.step();
+        self.ticks += 1;
+
+        let info = self.info(self.game.score() > score);
+        let rewards = &self.settings.rewards;
+        let mut reward = rewards.tick;
+        if info.ate {
+            reward += rewards.eat;
+        }
+        if info.win {
+            reward += rewards.win;
+        }
+        if info.crash {
+            reward += rewards.crash;
+        }
+        if info.missed_by.is_some() {
+            reward += rewards.miss;
+        }
+
+        (self.observe(), reward, self.done(), info)
+    }
+
+    // true once the match is won, lost or cut short
+    pub fn done(&self) -> bool {
+        self.game.game_over() || self.game.game_win() || self.truncated()
+    }
+
+    fn truncated(&self) -> bool {
+        self.settings.max_ticks.is_some_and(|max| self.ticks >= max)
+    }
+
+    fn info(&self, ate: bool) -> Info {
+        let missed_by = self.game.missed_by();
+        Info {
+            tick: self.game.tick(),
+            score: self.game.score(),
+            ate,
+            win: self.game.game_win(),
+            crash: self.game.game_over() && missed_by.is_none(),
+            missed_by,
+            truncated: self.truncated()
+        }
+    }
+
+    // the game being played, e.g. to draw it
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    // what the agent sees right now, in the chosen encoding
+    pub fn observe(&self) -> Observation {
+        match self.settings.encoding {
+            Encoding::Grid => self.grid(),
+            Encoding::Features => self.features()
+        }
+    }
+
+    // shape of the observations
+    pub fn observation_shape(&self) -> Vec<usize> {
+        match self.settings.encoding {
+            Encoding::Grid => {
+                let (width, height) = (self.game.width() as usize, self.game.height() as usize);
+                vec![Encoding::PLANES.len(), height, width]
+            }
+            Encoding::Features => vec![Encoding::FEATURES.len()]
+        }
+    }
+
+    fn grid(&self) -> Observation {
+        let shape = self.observation_shape();
+        let (width, height) = (self.game.width(), self.game.height());
+        let mut data = vec![0.0; shape.iter().product()];
+
+        let mut mark = |plane: usize, block: &Block| {
+            if block.x >= 0 && block.y >= 0 && block.x < width && block.y < height {
+                data[(plane * height as usize + block.y as usize) * width as usize + block.x as usize] = 1.0;
+            }
+        };
+
+        for block in self.game.snake().body() {
+            mark(0, block);
+        }
+        if let Some(head) = self.game.snake().body().next() {
+            mark(1, head);
+        }
+        for block in self.game.enemy().blocks() {
+            mark(2, block);
+        }
+        // where the food is & where it goes next, which gives away its direction
+        if let Some(food) = self.game.food() {
+            mark(3, &food);
+            if let Some((next, _)) = self.game.food_path(food, self.game.food_speed(), 1).first() {
+                mark(4, next);
+            }
+        }
+        for side in SIDES.iter() {
+            if let Some(paddle) = self.game.paddle(*side) {
+                for block in paddle.body() {
+                    mark(5, block);
+                }
+            }
+        }
+
+        Observation { shape, data }
+    }
+
+    fn features(&self) -> Observation {
+        let game = &self.game;
+        let (width, height) = (game.width() as f32, game.height() as f32);
+        let (head_x, head_y) = game.snake().head_position();
+        let heading = game.snake().head_direction();
+
+        let mut data = vec![head_x as f32 / width, head_y as f32 / height];
+        for dir in DIRECTIONS.iter() {
+            data.push(if *dir == heading { 1.0 } else { 0.0 });
+        }
+        // moves that would end the game (turning back counts as one)
+        for dir in DIRECTIONS.iter() {
+            let safe = *dir != heading.opposite() && game.check_if_snake_alive(0, Some(*dir));
+            data.push(if safe { 0.0 } else { 1.0 });
+        }
+
+        match game.food() {
+            Some(food) => {
+                let (speed_x, speed_y) = game.food_speed();
+                data.extend_from_slice(&[
+                    1.0,
+                    (food.x - head_x) as f32 / width,
+                    (food.y - head_y) as f32 / height,
+                    speed_x as f32,
+                    speed_y as f32
+                ]);
+            }
+            None => data.extend_from_slice(&[0.0; 5])
+        }
+
+        // paddle positions along their sides, as a share of the room between the corners
+        for side in SIDES.iter() {
+            let room = if side.is_vertical() { height - 2.0 } else { width - 2.0 };
+            match game.paddle(*side) {
+                Some(paddle) => {
+                    let (first, last) = paddle.span();
+                    data.extend_from_slice(&[
+                        1.0,
+                        ((first + last) as f32 / 2.0 - 1.0) / room,
+                        paddle.length() as f32 / room
+                    ]);
+                }
+                None => data.extend_from_slice(&[0.0; 3])
+            }
+        }
+
+        data.push(game.score() as f32 / game.settings().rules.win_score as f32);
+
+        Observation {
+            shape: self.observation_shape(),
+            data
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_out_of_range_are_refused() {
+        let mut settings = EnvSettings::default();
+        settings.game.board.width = 8;
+        assert!(Env::new(settings).is_err());
+    }
+
+    #[test]
+    fn new_plays_as_reset_with_seed_zero() {
+        let mut env = Env::new(EnvSettings::default()).unwrap();
+        let mut reset = Env::new(EnvSettings::default()).unwrap();
+        reset.reset(0);
+        for _ in 0..50 {
+            let (observation, reward, done, _) = env.step(Action::from(Input::Snake(Direction::Down)));
+            let (expected, expected_reward, expected_done, _) = reset.step(Action::from(Input::Snake(Direction::Down)));
+            assert_eq!(observation.data, expected.data);
+            assert_eq!((reward, done), (expected_reward, expected_done));
+        }
+    }
+
+    #[test]
+    fn two_snakes_are_refused() {
+        let mut settings = EnvSettings::default();
+        settings.game.rules.snakes = 2;
+        let err = Env::new(settings).err().unwrap();
+        assert!(err.to_string().contains("one snake"));
+    }
+
+    #[test]
+    fn the_action_space_has_the_turns_and_paddle_moves_only() {
+        let inputs: Vec<Input> = (1..Action::count()).map(|index| Action::from_index(index).unwrap().0[0]).collect();
+        assert_eq!(inputs.len(), 4 + 2 * 4);
+        assert!(inputs.iter().all(|input| matches!(input, Input::Snake(_) | Input::PaddleUp(_) | Input::PaddleDown(_))));
+        assert_eq!(Action::from_index(Action::count()), None);
+    }
+
+    #[test]
+    fn the_computer_answers_before_the_tick_it_plays_on() {
+        let env_settings = EnvSettings { autopilot: true, ..EnvSettings::default() };
+        let mut env = Env::new(env_settings.clone()).unwrap();
+        env.reset(5);
+
+        // the same match, with the computer asked right before every tick as in the window
+        let mut game = Game::new(&env_settings.game, 5);
+        let mut controllers: Vec<Box<dyn Controller>> = vec![
+            Box::new(Autopilot::new()),
+            Box::new(PaddleAi::new(Side::Left, Difficulty::Hard, 6)),
+            Box::new(PaddleAi::new(Side::Right, Difficulty::Hard, 7))
+        ];
+        for _ in 0..200 {
+            for controller in &mut controllers {
+                for input in controller.control(&game) {
+                    game.key_pressed(input);
+                }
+            }
+            game.step();
+            env.step(Action::default());
+            assert_eq!(env.game().view(), game.view());
+        }
+    }
+}
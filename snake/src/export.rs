@@ -0,0 +1,54 @@
+// replays drawn into images without a window: one frame as a PNG, or all of them as a GIF
+use std::io;
+
+use snake::raster::render;
+use snake::{Animation, Game, Replay};
+
+use crate::cli::Export;
+
+// play the replay tick by tick and draw it
+pub fn run(export: &Export) -> io::Result<()> {
+    let replay = Replay::load(&export.replay)?;
+    let settings = &replay.settings;
+    let mut game = Game::new(settings, replay.seed);
+    let mut playback = replay.playback();
+
+    let gif = export.out.extension().is_some_and(|extension| extension == "gif");
+    let mut animation = None;
+
+    loop {
+        // inputs recorded on the tick, before the frame is drawn
+        playback.give_due(&mut game);
+        let last = playback.finished(game.tick()) || export.tick == Some(game.tick());
+        let image = render(&game.view(), &settings.colors, export.block);
+
+        if gif {
+            if animation.is_none() {
+                animation = Some(Animation::create(&export.out, image.width(), image.height())?);
+            }
+            // each frame stays up as long as the game waits on it,
+            // and the last one a little longer before the animation loops
+            let over = game.game_over() || game.game_win();
+            let seconds = if over || last {
+                settings.speed.restart_time.max(1.0)
+            } else {
+                settings.speed.moving_period
+            };
+            if let Some(animation) = &mut animation {
+                animation.push(&image, seconds)?;
+            }
+        }
+
+        if last {
+            if !gif {
+                image.save_png(&export.out)?;
+            }
+            if export.tick.is_some_and(|tick| tick != game.tick()) {
+                eprintln!("the replay ends on tick {}, its last frame was saved", game.tick());
+            }
+            eprintln!("saved {}", export.out.display());
+            return Ok(());
+        }
+        game.advance();
+    }
+}
@@ -1,32 +1,36 @@
-// piston game engine libraries
-use piston_window::*;
-use piston_window::types::Color;
-
-// random number generator
-use rand::{thread_rng, Rng};
-
-// functions implemented in snake.rs & draw.rs
-use crate::objects::{Direction, Snake, Enemy, LeftPedal, RightPedal};
-use crate::draw::{draw_block, draw_rectangle};
-
-// colors
-// food -> blue, enemy -> red, border -> black, gameover -> light red, gamewin -> light blue
-const FOOD_COLOR: Color = [0.00, 0.00, 0.80, 1.0];      
-const BORDER_COLOR: Color = [0.00, 0.00, 0.00, 1.0];
-const SIDE_COLOR: Color = [0.00, 0.00, 0.00, 0.5];
-const GAMEOVER_COLOR: Color = [0.90, 0.00, 0.00, 0.5];
-const GAMEWIN_COLOR: Color = [0.00, 0.00, 0.90, 0.5];
-
-// moving speed & response time
-const MOVING_PERIOD: f64 = 0.3;
-const RESTART_TIME: f64 = 2.0;
+use std::collections::VecDeque;
+
+// seedable random number generator
+// -> the same seed & inputs always play out the same match
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
+
+// game objects, inputs & the view handed to front ends
+use crate::objects::{Block, Direction, Snake, Enemy, Paddle, Side};
+use crate::input::Input;
+use crate::settings::Settings;
+use crate::state::{State, COUNTDOWN};
+use crate::view::{Motion, View};
+
+// turns waiting for the next ticks, so that two quick presses make a U-turn
+const TURN_BUFFER: usize = 2;
+
+// ticks a single update plays at most, so that a long stall does not fast-forward the match
+const MAX_CATCH_UP: usize = 5;
  
 // game components
+// (cloning a game snapshots it: the clone plays on exactly as the game would, and so does a
+// game saved & loaded again, random number generator included)
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Game {
-    snake: Snake,        
+    // one per snake on the board (settings.rules.snakes), player one first
+    players: Vec<Player>,
 
-    l_pedal: LeftPedal,
-    r_pedal: RightPedal,    
+    // at most one per side
+    paddles: Vec<Paddle>,
+    // paddles whose key is held
+    held: Vec<Hold>,
 
     enemy: Enemy,    
 
@@ -39,22 +43,72 @@ pub struct Game {
     width: i32,
     height: i32,
 
-    game_over: bool,
-    game_win: bool,
+    // board size, speeds, paddles & win condition
+    settings: Settings,
+
+    // screen the game is on (a new game is already playing)
+    state: State,
+    // side whose paddle let the food through, if that is how the game was lost
+    missed_by: Option<Side>,
+    // player who won the round, if it was won
+    winner: Option<usize>,
+    // seconds since the last tick
     waiting_time: f64,
-    score: i32    
+    // food as it was before the last tick
+    previous_food: Option<Block>,
+    // seconds played since the round started
+    play_time: f64,
+
+    // number of steps (and restarts) since the game was created
+    // -> replays pin every input to the tick it happened on
+    tick: u64,
+
+    seed: u64,
+    rng: Pcg32
+}
+
+// a snake with its own turns & score
+#[derive(Clone, Serialize, Deserialize)]
+struct Player {
+    snake: Snake,
+    // turns pressed since the last tick, the first one is taken on the next tick
+    turns: VecDeque<Direction>,
+    score: i32,
+    // blocks as they were before the last tick
+    previous: Vec<Block>
+}
+
+// a paddle moving while its key is held
+// -> changes (a key going down or up) only count from the next tick, and every tick moves the
+//    same blocks whenever they were pressed, so that a replay moves the paddle the same way
+#[derive(Clone, Serialize, Deserialize)]
+struct Hold {
+    side: Side,
+    // direction it moves in during this tick (up, or down), none before its first tick
+    moving: Option<bool>,
+    // direction it moves in from the next tick, none once the key is up
+    next: Option<bool>,
+    // blocks it moves during this tick & the ones moved so far
+    moves: i32,
+    done: i32,
+    // share of a block left over from the ticks before
+    carry: f64
 }
 
 // game design
 impl Game {
     // starting up a new game
-    pub fn new(width: i32, height: i32) -> Game {
-        Game {            
-            snake: Snake::new(5, 5),    // start moving at (5,5)
-            l_pedal: LeftPedal::new(),  // initially located at the top of both sides
-            r_pedal: RightPedal::new(),                     
-            enemy: Enemy::new(),  
+    // every random decision is drawn from the seed
+    pub fn new(settings: &Settings, seed: u64) -> Game {
+        let (width, height) = (settings.board.width, settings.board.height);
+        let mut game = Game {
+            players: Game::new_players(settings, 5),    // start moving at (5,5)
+            paddles: Game::new_paddles(settings),  // initially located at the start of their sides
+            held: Vec::new(),
+            enemy: Enemy::new(width, height),
             waiting_time: 0.0,
+            previous_food: None,
+            play_time: 0.0,
             food_exists: true,
             food_x: 3,                 // start moving at (3,3)
             food_y: 3,
@@ -62,212 +116,557 @@ impl Game {
             food_speed_y: 1,                      
             width,
             height,
-            game_over: false,
-            game_win: false,
-            score: 0
+            settings: settings.clone(),
+            state: State::Playing,
+            missed_by: None,
+            winner: None,
+            tick: 0,
+            seed,
+            rng: Pcg32::seed_from_u64(seed)
+        };
+        game.hold_still();
+        game
+    }
+
+    // nothing has moved since the tick before
+    fn hold_still(&mut self) {
+        for player in &mut self.players {
+            player.previous = player.snake.body().copied().collect();
         }
+        self.previous_food = self.food();
     }
 
-    // keyboard inputs
-    pub fn key_pressed(&mut self, key: Key) {
-        if self.game_over {
+    // player inputs
+    // (the snake & paddles only move while playing, a restart counts down to the new round)
+    pub fn key_pressed(&mut self, input: Input) {
+        if input == Input::Restart {
+            self.restart();
+            self.state = State::Countdown(COUNTDOWN);
+            return;
+        }
+        // (a key going up counts even while paused, or the paddle would go on after it)
+        if let Input::Release(side) = input {
+            if let Some(hold) = self.held.iter_mut().find(|hold| hold.side == side) {
+                hold.next = None;
+            }
+            return;
+        }
+        if !self.state.is_playing() {
             return;
         }
 
-        // snake control
-        let dir = match key {
-            Key::Up => Some(Direction::Up),
-            Key::Down => Some(Direction::Down),
-            Key::Left => Some(Direction::Left),
-            Key::Right => Some(Direction::Right),
-            _ => Some(self.snake.head_direction())
-        };
+        match input {
+            // snake control
+            // (only queues the turn: the snake moves on the ticks alone)
+            Input::Snake(dir) => self.turn(0, dir),
+            Input::SecondSnake(dir) => self.turn(1, dir),
+
+            // paddle control
+            // (leaves the snake alone, so computer paddles do not speed it up)
+            Input::PaddleUp(side) => {
+                if let Some(paddle) = self.paddle_mut(side) {
+                    paddle.move_up();
+                }
+            }
+            Input::PaddleDown(side) => {
+                if let Some(paddle) = self.paddle_mut(side) {
+                    paddle.move_down();
+                }
+            }
 
-        // left pedal control
-        match key {
-            Key::Q => self.l_pedal.move_up(),
-            Key::A => self.l_pedal.move_down(),
-            _ => ()
-        };
+            Input::HoldUp(side) => self.hold(side, true),
+            Input::HoldDown(side) => self.hold(side, false),
+
+            Input::Restart | Input::Release(_) => ()
+        }
+    }
 
-        // right pedal control
-        match key {
-            Key::R => self.r_pedal.move_up(),
-            Key::F => self.r_pedal.move_down(),
-            _ => ()
+    // a turn of a player's snake, taken on the ticks to come
+    // (nothing happens if there is no such snake on the board)
+    fn turn(&mut self, index: usize, dir: Direction) {
+        let player = match self.players.get_mut(index) {
+            Some(player) => player,
+            None => return
         };
+        // direction the snake has once the turns before are taken
+        let heading = player.turns.back().copied().unwrap_or_else(|| player.snake.head_direction());
 
-        // prevent a snake from turning to the opposite direction
-        if dir.unwrap() == self.snake.head_direction().opposite() {
+        // prevent a snake from turning to the opposite direction,
+        // and ignore presses that do not turn it
+        if dir == heading.opposite() || dir == heading || player.turns.len() >= TURN_BUFFER {
             return;
         }
-
-        // update snake status
-        self.update_snake(dir);
+        player.turns.push_back(dir);
     }
 
-    // draw components
-    pub fn draw(&self, con: &Context, g: &mut G2d) {
-        // snake
-        self.snake.draw(con, g);        
-
-        // pedals
-        self.l_pedal.draw(con, g);
-        self.r_pedal.draw(con, g);
-
-        // enemy
-        self.enemy.draw(con, g);
+    // a paddle key going down: one move right away, then the paddle keeps moving from the next tick
+    fn hold(&mut self, side: Side, up: bool) {
+        match self.paddle_mut(side) {
+            Some(paddle) if up => paddle.move_up(),
+            Some(paddle) => paddle.move_down(),
+            None => return
+        }
+        match self.held.iter_mut().find(|hold| hold.side == side) {
+            Some(hold) => hold.next = Some(up),
+            None => self.held.push(Hold {
+                side,
+                moving: None,
+                next: Some(up),
+                moves: 0,
+                done: 0,
+                carry: 0.0
+            })
+        }
+    }
 
-        // food 
-        if self.food_exists {
-            draw_block(FOOD_COLOR, self.food_x, self.food_y, con, g);            
+    // move the held paddles along, as far as the tick has gone
+    // (the whole tick's blocks once it is over)
+    fn move_held(&mut self, share: f64) {
+        for index in 0..self.held.len() {
+            let hold = &mut self.held[index];
+            let up = match hold.moving {
+                Some(up) => up,
+                None => continue
+            };
+            let target = ((hold.moves as f64 * share.min(1.0)).floor() as i32).min(hold.moves);
+            let blocks = target - hold.done;
+            hold.done = target;
+            let side = hold.side;
+            if let Some(paddle) = self.paddle_mut(side) {
+                if up {
+                    paddle.slide_up(blocks);
+                } else {
+                    paddle.slide_down(blocks);
+                }
+            }
         }
+    }
         
-        // boundaries
-        draw_rectangle(BORDER_COLOR, 0, 0, self.width, 1, con, g);
-        draw_rectangle(BORDER_COLOR, 0, self.height - 1, self.width, 1, con, g);
-        draw_rectangle(SIDE_COLOR, 0, 0, 1, self.height, con, g);
-        draw_rectangle(SIDE_COLOR, self.width - 1, 0, 1, self.height, con, g);
-
-        // game over screen
-        if self.game_over {
-            draw_rectangle(GAMEOVER_COLOR, 0, 0, self.width, self.height, con, g);
+    // a tick is over: the held paddles finish its blocks and take on their keys' changes
+    fn next_held_tick(&mut self) {
+        self.move_held(1.0);
+        let per_tick = self.settings.speed.paddle_rate * self.settings.speed.moving_period;
+        self.held.retain(|hold| hold.next.is_some());
+        for hold in &mut self.held {
+            hold.moving = hold.next;
+            hold.carry += per_tick;
+            hold.moves = hold.carry.floor() as i32;
+            hold.carry -= hold.moves as f64;
+            hold.done = 0;
         }
+    }
 
-        // game win screen
-        if self.game_win {
-            draw_rectangle(GAMEWIN_COLOR, 0, 0, self.width, self.height, con, g);          
+    // snapshot of every component for drawing
+    pub fn view(&self) -> View {
+        View {
+            width: self.width,
+            height: self.height,
+            snakes: self.players.iter().map(|player| player.snake.body().copied().collect()).collect(),
+            paddles: self.paddles.iter()
+                .map(|paddle| (paddle.side(), paddle.body().copied().collect()))
+                .collect(),
+            enemies: self.enemy.blocks().copied().collect(),
+            food: self.food(),
+            scores: self.scores(),
+            winner: self.winner,
+            seed: self.seed,
+            game_over: self.game_over(),
+            game_win: self.game_win()
         }
-
     }
 
     // update components
+    // (only the countdown & the round move with the clock, a game over or win stays
+    // until an Input::Restart)
     pub fn update(&mut self, delta_time: f64) {
-        self.waiting_time += delta_time;
-
-        // if score reaches 21, win
-        if self.score == 21 {
-            self.game_win = true;                                                            
-        }
+        self.update_with(delta_time, |_| true);
+    }
 
-        // if game over, restart the game after restart time (2.0)
-        if self.game_over {
-            if self.waiting_time > RESTART_TIME {
-                self.restart();
+    // update components, with a tick every moving period of play whatever the frame rate
+    // -> a slow frame catches up with several ticks (at most MAX_CATCH_UP, the time beyond is dropped)
+    // before_tick is called ahead of every tick, e.g. to give the inputs due on it,
+    // and the ticks stop for this update if it answers false
+    pub fn update_with<F: FnMut(&mut Game) -> bool>(&mut self, delta_time: f64, mut before_tick: F) {
+        if let State::Countdown(left) = &mut self.state {
+            *left -= delta_time;
+            if *left <= 0.0 {
+                self.state = State::Playing;
             }
             return;
         }
-
-        // if game win, restart the game after restart time (2.0)
-        if self.game_win {            
-            if self.waiting_time > RESTART_TIME {
-                self.restart();
-            }
+        if !self.state.is_playing() {
             return;
         }
+        self.waiting_time += delta_time;
+        self.play_time += delta_time;
+
+        // update
+        let period = self.settings.speed.moving_period;
+        let mut ticks = 0;
+        while self.waiting_time >= period && self.state.is_playing() {
+            if ticks == MAX_CATCH_UP {
+                self.waiting_time %= period;
+                break;
+            }
+            if !before_tick(self) {
+                break;
+            }
+            self.waiting_time -= period;
+            self.step();
+            ticks += 1;
+        }
+        if self.state.is_playing() {
+            self.move_held(self.waiting_time / period);
+        }
+    }
 
-        // if snake eats the food, generate another food
-        if !self.food_exists {
-            self.add_food();            
+    // share of the moving period gone by since the last tick
+    pub fn progress(&self) -> f64 {
+        (self.waiting_time / self.settings.speed.moving_period).clamp(0.0, 1.0)
+    }
+
+    // where the snakes & the food were before the last tick, and how far the clock is towards
+    // the next one (front ends draw them in between, for smooth motion)
+    pub fn motion(&self) -> Motion {
+        Motion {
+            snakes: self.players.iter().map(|player| player.previous.clone()).collect(),
+            food: self.previous_food,
+            progress: self.progress()
+        }
+    }
+
+    // one move of the snakes & the food, whatever the clock says
+    // (front ends without a clock, e.g. training agents, call this instead of update)
+    pub fn step(&mut self) {
+        if !self.state.is_playing() {
+            return;
+        }
+        self.tick += 1;
+        self.hold_still();
+        self.next_held_tick();
+        let turns: Vec<Option<Direction>> = self.players.iter_mut().map(|player| player.turns.pop_front()).collect();
+        self.update_snakes(&turns);
+        // (food that found no room when it was eaten comes back as soon as there is some)
+        if !self.food_exists && self.state.is_playing() {
+            self.add_food();
         }
+        self.food_moving();
+    }
 
-        // update 
-        if self.waiting_time > MOVING_PERIOD {
-            self.update_snake(None);
-            self.food_moving();            
+    // one tick without a clock: a step, or the restart of a finished game
+    // (with nobody to press a key, a finished round restarts right away)
+    pub fn advance(&mut self) {
+        if self.game_over() || self.game_win() {
+            self.restart();
+            return;
+        }
+        // nothing to count down with
+        if let State::Countdown(_) = self.state {
+            self.state = State::Playing;
         }
+        self.step();
     }
 
     // food moving function
     fn food_moving(&mut self) {
-                
-        // only ceiling and floor
-        let top_bound = 0;
-        let bottom_bound = self.height;        
-
         // moving with a pattern        
         self.food_x += self.food_speed_x;        
         self.food_y += self.food_speed_y;
         
-        if self.food_y <= top_bound+1 || self.food_y >= bottom_bound-2 {
+        // ceiling and floor, then left & right sides
+        for side in self.sides_reached(self.food_x, self.food_y) {
+            self.food_reaches(side);
+        }
+    }
+
+    // sides the food is next to at a position, the ceiling or floor first
+    // (shared by food_moving & food_path, so that the food bounces where it is foreseen to)
+    fn sides_reached(&self, x: i32, y: i32) -> Vec<Side> {
+        let mut sides = Vec::new();
+        if y <= 1 {
+            sides.push(Side::Top);
+        } else if y >= self.height - 2 {
+            sides.push(Side::Bottom);
+        }
+        if x <= 1 {
+            sides.push(Side::Left);
+        } else if x >= self.width - 2 {
+            sides.push(Side::Right);
+        }
+        sides
+    }
+
+    // the food is next to a side: it bounces off the paddle guarding the side (or off the
+    // wall if there is none), but if the paddle misses it, game over
+    fn food_reaches(&mut self, side: Side) {
+        // position of the food along the side
+        let along = if side.is_vertical() { self.food_y } else { self.food_x };
+
+        let blocked = match self.paddle(side) {
+            Some(paddle) => paddle.block_food(along + 1) || paddle.block_food(along - 1),
+            None => true
+        };
+
+        if !blocked {
+            // (a round the snake already won or lost on this tick stays that way)
+            if self.state.is_playing() {
+                self.state = State::GameOver;
+            }
+            self.missed_by = self.missed_by.or(Some(side));
+        } else if side.is_vertical() {
+            self.food_speed_x = -self.food_speed_x;
+        } else {
             self.food_speed_y = -self.food_speed_y;
         }
+    }
 
-        // blocking with the left pedal 
-        if self.food_x-1 == 0 {
-            if self.l_pedal.block_food(self.food_y+1) || self.l_pedal.block_food(self.food_y-1) {
-                self.food_speed_x = -self.food_speed_x;
-            } else {
-                self.game_over = true;
-            }            
+    // the paddle guarding a side, if any
+    pub fn paddle(&self, side: Side) -> Option<&Paddle> {
+        self.paddles.iter().find(|paddle| paddle.side() == side)
+    }
+
+    fn paddle_mut(&mut self, side: Side) -> Option<&mut Paddle> {
+        self.paddles.iter_mut().find(|paddle| paddle.side() == side)
+    }
+
+    // the snakes of the settings, the first one facing right from (corner, corner)
+    // -> the second one starts from the opposite corner, facing left
+    fn new_players(settings: &Settings, corner: i32) -> Vec<Player> {
+        let (width, height) = (settings.board.width, settings.board.height);
+        let starts = vec![
+            Snake::new(corner, corner),
+            Snake::facing(width - 1 - corner, height - 1 - corner, Direction::Left)
+        ];
+        starts.into_iter()
+            .take(settings.rules.snakes)
+            .map(|snake| Player {
+                snake,
+                turns: VecDeque::new(),
+                score: 0,
+                previous: Vec::new()
+            })
+            .collect()
+    }
+
+    // the paddles laid out in the settings
+    fn new_paddles(settings: &Settings) -> Vec<Paddle> {
+        let (width, height) = (settings.board.width, settings.board.height);
+        settings.paddles.iter()
+            .map(|paddle| Paddle::new(paddle.side, paddle.length, paddle.speed, width, height))
+            .collect()
+    }
+
+    // where the food goes over the next ticks, from a position & speed
+    // -> every side bounces it back, as if every paddle blocks it
+    // each entry is the position after a tick and the speed it leaves with
+    pub fn food_path(&self, food: Block, speed: (i32, i32), ticks: usize) -> Vec<(Block, (i32, i32))> {
+        let (mut x, mut y) = (food.x, food.y);
+        let (mut speed_x, mut speed_y) = speed;
+
+        let mut path = Vec::with_capacity(ticks);
+        for _ in 0..ticks {
+            // same pattern as food_moving
+            x += speed_x;
+            y += speed_y;
+            for side in self.sides_reached(x, y) {
+                if side.is_vertical() {
+                    speed_x = -speed_x;
+                } else {
+                    speed_y = -speed_y;
+                }
+            }
+            path.push((Block { x, y }, (speed_x, speed_y)));
         }
+        path
+    }
 
-        // blocking with the right pedal
-        if self.food_x+2 == self.width {
-            if self.r_pedal.block_food(self.food_y+1) || self.r_pedal.block_food(self.food_y-1) {
-               self.food_speed_x = -self.food_speed_x;
-            } else {
-                self.game_over = true;
-            }       
+    // return the snake of player one
+    pub fn snake(&self) -> &Snake {
+        &self.players[0].snake
+    }
+
+    // return every snake, player one first
+    pub fn snakes(&self) -> impl Iterator<Item = &Snake> {
+        self.players.iter().map(|player| &player.snake)
+    }
+
+    // return the enemies
+    pub fn enemy(&self) -> &Enemy {
+        &self.enemy
+    }
+
+    // return the size of the board
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    // return the food's position, if there is one
+    pub fn food(&self) -> Option<Block> {
+        if self.food_exists {
+            Some(Block { x: self.food_x, y: self.food_y })
+        } else {
+            None
         }
+    }
+
+    // return the food's speed (blocks per tick, along x & y)
+    pub fn food_speed(&self) -> (i32, i32) {
+        (self.food_speed_x, self.food_speed_y)
+    }
+
+    // return the score of player one
+    pub fn score(&self) -> i32 {
+        self.players[0].score
+    }
+
+    // return every player's score, player one first
+    pub fn scores(&self) -> Vec<i32> {
+        self.players.iter().map(|player| player.score).collect()
+    }
+
+    // return the player who won the round, if it was won
+    pub fn winner(&self) -> Option<usize> {
+        self.winner
+    }
+
+    // return the screen the game is on
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    // move to another screen (menus, initials entry), as the front end asks
+    pub fn set_state(&mut self, state: State) {
+        self.state = state;
+    }
+
+    // return game over state (also while typing initials after it)
+    pub fn game_over(&self) -> bool {
+        match self.state {
+            State::GameOver => true,
+            State::HighScoreEntry { won, .. } => !won,
+            _ => false
+        }
+    }
+
+    // return game win state
+    pub fn game_win(&self) -> bool {
+        match self.state {
+            State::Win => true,
+            State::HighScoreEntry { won, .. } => won,
+            _ => false
+        }
+    }
+
+    // return the side whose paddle missed the food, if that ended the game
+    pub fn missed_by(&self) -> Option<Side> {
+        self.missed_by
+    }
 
+    // return the seconds played since the round started
+    pub fn play_time(&self) -> f64 {
+        self.play_time
     }
 
-    // return score
-    pub fn score(&mut self) -> i32 {
-        return self.score;
+    // return the current tick
+    pub fn tick(&self) -> u64 {
+        self.tick
     }
 
-    // return game over state
-    pub fn game_over(&mut self) -> bool {
-        return self.game_over
+    // return the settings the game plays by
+    pub fn settings(&self) -> &Settings {
+        &self.settings
     }
 
-    // return game over state
-    pub fn game_win(&mut self) -> bool {
-        return self.game_win
+    // return the seed the game was started with
+    pub fn seed(&self) -> u64 {
+        self.seed
     }
 
-    // check if snake eats
-    pub fn check_eating(&mut self) -> bool {
+    // check if a player's snake eats
+    pub fn check_eating(&mut self, index: usize) -> bool {
         // head position of the snake
-        let (head_x, head_y): (i32, i32) = self.snake.head_position();        
+        let (head_x, head_y): (i32, i32) = self.players[index].snake.head_position();
 
         // if it matches 
         if self.food_exists && self.food_x == head_x && self.food_y == head_y {
             self.food_exists = false;
-            self.snake.restore_tail();
-            if self.score % 2 == 1 {
+            let player = &mut self.players[index];
+            player.snake.restore_tail();
+            player.score += 1;
+            // if score reaches the win score, win
+            if player.score == self.settings.rules.win_score {
+                self.state = State::Win;
+                self.winner = Some(index);
+            }
+            // another enemy every second food, whichever snake eats it
+            let eaten: i32 = self.players.iter().map(|player| player.score).sum();
+            if eaten % 2 == 0 {
                 self.enemy.add_enemy();
             }            
-            self.score += 1;
+            // generate another food
+            self.add_food();
             return true;
         }
-        return false;
+        false
     }
 
-    // check if snake contacts with itself or with enemy
-    fn check_if_snake_alive(&mut self, dir: Option<Direction>) -> bool {
-        let (next_x, next_y) = self.snake.next_head(dir);        
-
-        if self.snake.overlap_tail(next_x, next_y) 
-            || self.enemy.contact(next_x, next_y) {
-            return false;
+    // check if a player's snake contacts with itself, with enemy or with the other snake
+    // (false if there is no such snake)
+    pub fn check_if_snake_alive(&self, index: usize, dir: Option<Direction>) -> bool {
+        match self.players.get(index) {
+            Some(player) => {
+                let (next_x, next_y) = player.snake.next_head(dir);
+                self.open(index, next_x, next_y)
+            }
+            None => false
         }
+    }
 
-        next_x > 0 && next_y > 0 && next_x < self.width - 1 && next_y < self.height - 1
+    // true if a player's snake can move its head to a block
+    // -> its own tail end moves away at the same time (as in Snake::overlap_tail),
+    //    but every block of another snake is in the way
+    fn open(&self, index: usize, x: i32, y: i32) -> bool {
+        let inside = x > 0 && y > 0 && x < self.width - 1 && y < self.height - 1;
+        inside && !self.enemy.contact(x, y) && self.players.iter().enumerate().all(|(other, player)| {
+            if other == index {
+                !player.snake.overlap_tail(x, y)
+            } else {
+                !player.snake.body().any(|block| block.x == x && block.y == y)
+            }
+        })
     }
 
     // adding another food in a random spot 
-    // 5 block size away from boundaries -> for pedals to move
+    // 5 block size away from boundaries -> for paddles to move
+    // (none if the snakes leave no room, step tries again on the ticks after)
     fn add_food(&mut self) {
-        let mut rng = thread_rng();
+        let rng = &mut self.rng;
+        let players = &self.players;
+        let taken = |x: i32, y: i32| players.iter().any(|player| player.snake.overlap_tail(x, y));
+
+        // (anywhere inside the walls once the snakes fill that area, on the smallest boards)
+        let inner = (5, self.width - 5, 5, self.height - 5);
+        let inside = (1, self.width - 1, 1, self.height - 1);
+        let free = |(left, right, top, bottom): (i32, i32, i32, i32)| {
+            (left..right).any(|x| (top..bottom).any(|y| !taken(x, y)))
+        };
+        let (left, right, top, bottom) = match [inner, inside].iter().copied().find(|area| free(*area)) {
+            Some(area) => area,
+            None => {
+                self.food_exists = false;
+                return;
+            }
+        };
 
-        let mut new_x = rng.gen_range(5, self.width - 5);
-        let mut new_y = rng.gen_range(5, self.height - 5);
-        while self.snake.overlap_tail(new_x, new_y) {
-            new_x = rng.gen_range(5, self.width - 5);
-            new_y = rng.gen_range(5, self.height - 5);
+        let mut new_x = rng.gen_range(left, right);
+        let mut new_y = rng.gen_range(top, bottom);
+        while taken(new_x, new_y) {
+            new_x = rng.gen_range(left, right);
+            new_y = rng.gen_range(top, bottom);
         }
 
         self.food_x = new_x;
@@ -275,34 +674,322 @@ impl Game {
         self.food_exists = true;
     }
 
-    // updating snake's status
-    fn update_snake(&mut self, dir: Option<Direction>) {
-        // if snake is alive,
-        if self.check_if_snake_alive(dir) {
-            // always on the move & checking if it eats an apple
-            self.snake.move_forward(dir);
-            self.check_eating();            
-        } else {
-            self.game_over = true;
+    // updating the snakes' status, all of them moving at once
+    // a snake crashes into the walls, the enemies, its own body & every block of the other snake,
+    // and two heads going to the same block (head to head) crash both
+    // -> on a crash nobody moves: with two snakes the one left wins, if both crash it is game over
+    fn update_snakes(&mut self, turns: &[Option<Direction>]) {
+        let heads: Vec<(i32, i32)> = self.players.iter()
+            .zip(turns)
+            .map(|(player, turn)| player.snake.next_head(*turn))
+            .collect();
+        let alive: Vec<usize> = (0..self.players.len())
+            .filter(|index| {
+                let (x, y) = heads[*index];
+                let head_to_head = heads.iter().enumerate().any(|(other, head)| other != *index && *head == (x, y));
+                self.open(*index, x, y) && !head_to_head
+            })
+            .collect();
+
+        if alive.len() < self.players.len() {
+            if self.players.len() > 1 && alive.len() == 1 {
+                self.state = State::Win;
+                self.winner = Some(alive[0]);
+            } else {
+                self.state = State::GameOver;
+            }
+            return;
+        }
+
+        // if every snake is alive,
+        // always on the move & checking if one eats an apple
+        for (player, turn) in self.players.iter_mut().zip(turns) {
+            player.snake.move_forward(*turn);
+        }
+        for index in 0..self.players.len() {
+            self.check_eating(index);
         }
-        self.waiting_time = 0.0;
     }
 
     // restart the game
     // reinitialize all the variables
     fn restart(&mut self) {
-        self.snake = Snake::new(2, 2);
-        self.l_pedal = LeftPedal::new();
-        self.r_pedal = RightPedal::new(); 
-        self.enemy = Enemy::new();     
+        self.tick += 1;
+        self.players = Game::new_players(&self.settings, 2);
+        self.paddles = Game::new_paddles(&self.settings);
+        self.held.clear();
+        self.enemy = Enemy::new(self.width, self.height);
         self.waiting_time = 0.0;
+        self.play_time = 0.0;
         self.food_exists = true;
-        self.food_x = 10;
-        self.food_y = 10;
+        // a third of the way into the board, (10,10) on the default one
+        self.food_x = self.width / 3;
+        self.food_y = self.height / 3;
         self.food_speed_x = 1;
         self.food_speed_y = 1;
-        self.game_over = false;
-        self.game_win = false;
-        self.score = 0;
+        self.state = State::Playing;
+        self.missed_by = None;
+        self.winner = None;
+        self.hold_still();
+    }
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autopilot::Autopilot;
+    use crate::controller::Controller;
+
+    // a match played with the same keys every time, whatever happens on the board
+    fn play(game: &mut Game, frames: u32) {
+        let turns = [Direction::Up, Direction::Left, Direction::Down, Direction::Right];
+        let pedals = [Input::PaddleUp(Side::Left), Input::PaddleDown(Side::Right), Input::PaddleDown(Side::Left), Input::PaddleUp(Side::Right)];
+        for frame in 0..frames {
+            if frame % 7 == 0 {
+                game.key_pressed(Input::Snake(turns[(frame / 7) as usize % turns.len()]));
+            }
+            if frame % 3 == 0 {
+                game.key_pressed(pedals[(frame / 3) as usize % pedals.len()]);
+            }
+            game.update(0.1);
+        }
+    }
+
+    // everything on the board (not the seed, which tells games apart on its own)
+    fn state(game: &Game) -> String {
+        let view = game.view();
+        format!("{:?} {:?} {:?} {:?} {:?}", view.snakes, view.paddles, view.enemies, view.food, view.scores)
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn same_seed_and_inputs_play_the_same_match() {
+        let (mut first, mut second) = (Game::new(&Settings::default(), 42), Game::new(&Settings::default(), 42));
+        play(&mut first, 3000);
+        play(&mut second, 3000);
+        assert_eq!(state(&first), state(&second));
+    }
+
+    // where the food lands, time after time
+    fn food_spots(seed: u64) -> Vec<(i32, i32)> {
+        let mut game = Game::new(&Settings::default(), seed);
+        (0..20)
+            .map(|_| {
+                game.add_food();
+                (game.food_x, game.food_y)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn the_food_lands_where_the_seed_says() {
+        assert_eq!(food_spots(1), food_spots(1));
+        assert_ne!(food_spots(1), food_spots(2));
+    }
+
+    // the clock runs until the round is over (the snake goes straight into the wall)
+    fn play_round(game: &mut Game) {
+        let period = game.settings().speed.moving_period;
+        for _ in 0..100 {
+            game.update(period + 0.01);
+            if !game.state().is_playing() {
+                return;
+            }
+        }
+        panic!("the round never ended");
+    }
+
+    #[test]
+    fn a_round_over_stays_over_until_restarted() {
+        let mut game = Game::new(&Settings::default(), 3);
+        play_round(&mut game);
+        assert_eq!(*game.state(), State::GameOver);
+        let tick = game.tick();
+        game.update(60.0);
+        game.key_pressed(Input::Snake(Direction::Down));
+        assert_eq!((game.state(), game.tick()), (&State::GameOver, tick));
+
+        // a restart counts down, then the new round plays
+        game.key_pressed(Input::Restart);
+        assert_eq!(*game.state(), State::Countdown(COUNTDOWN));
+        game.update(COUNTDOWN - 0.5);
+        assert!(matches!(game.state(), State::Countdown(_)));
+        game.update(1.0);
+        assert_eq!(*game.state(), State::Playing);
+        assert_eq!(game.score(), 0);
+    }
+
+    #[test]
+    fn nothing_moves_while_paused() {
+        let mut game = Game::new(&Settings::default(), 3);
+        game.set_state(State::Paused(0));
+        let (tick, view) = (game.tick(), state(&game));
+        game.key_pressed(Input::Snake(Direction::Down));
+        game.key_pressed(Input::PaddleDown(Side::Left));
+        game.update(10.0);
+        game.step();
+        assert_eq!((game.tick(), state(&game)), (tick, view));
+    }
+
+    // where the left paddle is once the game reaches a tick, in frames of some seconds
+    fn held_paddle(frame: f64, release_at: u64, ticks: u64) -> (i32, i32) {
+        let mut game = Game::new(&Settings::default(), 3);
+        game.key_pressed(Input::HoldDown(Side::Left));
+        while game.tick() < ticks {
+            if game.tick() == release_at {
+                game.key_pressed(Input::Release(Side::Left));
+            }
+            game.update(frame);
+        }
+        game.paddle(Side::Left).unwrap().span()
+    }
+
+    #[test]
+    fn held_paddles_move_at_their_rate_whatever_the_frames() {
+        let start = Game::new(&Settings::default(), 3).paddle(Side::Left).unwrap().span();
+        let (first, _) = held_paddle(0.31, 10, 4);
+        // one block on the key press, then paddle_rate x moving_period (3) every tick after the first
+        assert_eq!(first - start.0, 1 + 3 * 3);
+        assert_eq!(held_paddle(0.31, 10, 4), held_paddle(0.031, 10, 4));
+        assert_eq!(held_paddle(0.31, 10, 4), held_paddle(0.1, 10, 4));
+        // (a released key stops the paddle from the next tick on)
+        assert_eq!(held_paddle(0.31, 3, 8), held_paddle(0.31, 3, 4));
+    }
+
+    #[test]
+    fn quick_turns_wait_for_the_next_ticks() {
+        let mut game = Game::new(&Settings::default(), 3);
+        assert_eq!(game.snake().head_direction(), Direction::Right);
+        // (a reverse turn is ignored)
+        game.key_pressed(Input::Snake(Direction::Left));
+        // (two quick turns make a U-turn over the next two ticks)
+        game.key_pressed(Input::Snake(Direction::Down));
+        game.key_pressed(Input::Snake(Direction::Left));
+        // (turns past TURN_BUFFER are dropped)
+        game.key_pressed(Input::Snake(Direction::Up));
+        assert_eq!(game.snake().head_direction(), Direction::Right);
+        game.step();
+        assert_eq!(game.snake().head_direction(), Direction::Down);
+        game.step();
+        assert_eq!(game.snake().head_direction(), Direction::Left);
+        game.step();
+        assert_eq!(game.snake().head_direction(), Direction::Left);
+    }
+
+    #[test]
+    fn a_slow_frame_catches_up_with_at_most_a_few_ticks() {
+        let mut game = Game::new(&Settings::default(), 3);
+        let period = game.settings.speed.moving_period;
+        game.update(period * 2.5);
+        assert_eq!(game.tick(), 2);
+        assert!((game.progress() - 0.5).abs() < 1e-9);
+        // (the time beyond MAX_CATCH_UP ticks is dropped)
+        game.update(period * 100.0);
+        assert_eq!(game.tick(), 2 + MAX_CATCH_UP as u64);
+        assert!(game.progress() < 1.0);
+        // (and no tick is played once before_tick says no)
+        game.update_with(period * 3.0, |_| false);
+        assert_eq!(game.tick(), 2 + MAX_CATCH_UP as u64);
+    }
+
+    // a two-player game with the snakes placed by hand
+    fn two_snakes(first: Snake, second: Snake) -> Game {
+        let mut settings = Settings::default();
+        settings.rules.snakes = 2;
+        let mut game = Game::new(&settings, 3);
+        game.players[0].snake = first;
+        game.players[1].snake = second;
+        game
+    }
+
+    #[test]
+    fn snakes_meeting_head_to_head_both_crash() {
+        let mut game = two_snakes(Snake::facing(9, 15, Direction::Right), Snake::facing(13, 15, Direction::Left));
+        game.step();
+        assert_eq!(*game.state(), State::GameOver);
+        assert_eq!(game.winner(), None);
+    }
+
+    #[test]
+    fn a_snake_running_into_the_other_loses_to_it() {
+        let mut game = two_snakes(Snake::facing(9, 15, Direction::Right), Snake::facing(11, 16, Direction::Up));
+        game.step();
+        assert_eq!(*game.state(), State::Win);
+        assert_eq!(game.winner(), Some(1));
+    }
+
+    #[test]
+    fn the_food_stays_on_the_smallest_board() {
+        let mut settings = Settings::default();
+        settings.board.width = 12;
+        settings.board.height = 12;
+        let mut game = Game::new(&settings, 5);
+        // (the autopilot eats, so the food keeps landing somewhere new)
+        let mut autopilot = Autopilot::new();
+        for _ in 0..1000 {
+            for input in autopilot.control(&game) {
+                game.key_pressed(input);
+            }
+            game.advance();
+            if let Some(food) = game.food() {
+                assert!(food.x >= 1 && food.x <= 10 && food.y >= 1 && food.y <= 10, "food left the board at {:?}", food);
+            }
+        }
+    }
+
+    #[test]
+    fn the_food_comes_back_once_a_full_board_has_room() {
+        let mut settings = Settings::default();
+        settings.board.width = 12;
+        settings.board.height = 12;
+        let mut game = Game::new(&settings, 5);
+
+        // a snake growing over every block inside the walls, row by row
+        let mut snake = Snake::new(1, 1);
+        for row in 1..=10 {
+            let across = if row % 2 == 1 { Direction::Right } else { Direction::Left };
+            let moves = if row == 1 { 8 } else { 9 };
+            for _ in 0..moves {
+                snake.move_forward(Some(across));
+                snake.restore_tail();
+            }
+            if row < 10 {
+                snake.move_forward(Some(Direction::Down));
+                snake.restore_tail();
+            }
+        }
+        assert_eq!(snake.body().count(), 100);
+        let tail = *snake.body().last().unwrap();
+        game.players[0].snake = snake;
+        // (the tail moves off its block on the next tick, so the food may go there)
+        game.add_food();
+        assert_eq!(game.food(), Some(tail));
+
+        // food that found no room comes back on the next tick that has some
+        game.players[0].snake = Snake::new(5, 5);
+        game.food_exists = false;
+        game.step();
+        assert!(game.food().is_some());
+    }
+
+    #[test]
+    fn the_food_goes_where_its_path_says() {
+        // (no paddles: the walls bounce the food back, as the path has it)
+        let mut settings = Settings::default();
+        settings.paddles.clear();
+        let mut game = Game::new(&settings, 4);
+        let food = game.food().unwrap();
+        let path = game.food_path(food, game.food_speed(), 100);
+        for (block, speed) in path {
+            game.food_moving();
+            assert_eq!((game.food().unwrap(), game.food_speed()), (block, speed));
+        }
+    }
+
+    #[test]
+    fn either_snake_can_be_checked_for_a_crash() {
+        let game = two_snakes(Snake::facing(9, 15, Direction::Right), Snake::facing(11, 16, Direction::Up));
+        assert!(!game.check_if_snake_alive(0, None));
+        assert!(game.check_if_snake_alive(1, None));
+        assert!(!game.check_if_snake_alive(2, None));
+    }
+}
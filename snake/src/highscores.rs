@@ -0,0 +1,162 @@
+// best scores ever played on this machine, kept in the user's data directory
+// (e.g. ~/.local/share/snake/highscores.toml on Linux)
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+// entries kept in the table
+pub const TABLE_SIZE: usize = 10;
+// letters in a player's initials
+pub const NAME_LENGTH: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighScore {
+    pub name: String,
+    pub score: i32,
+    // seconds the round lasted
+    pub duration: f64,
+    pub seed: u64,
+    // when it was played, e.g. "2024-05-01 18:30 UTC"
+    pub date: String
+}
+
+// best first
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HighScores {
+    #[serde(default)]
+    pub scores: Vec<HighScore>
+}
+
+impl HighScores {
+    // where the table lives, if the system has a data directory
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("snake").join("highscores.toml"))
+    }
+
+    // read the table (empty if there is no file yet)
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<HighScores> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(HighScores::default()),
+            Err(err) => return Err(err)
+        };
+        toml::from_str(&text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    // write the table, creating its directory if needed
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let text = toml::to_string(self).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        fs::write(path, text)
+    }
+
+    // true if the score makes it into the table
+    pub fn qualifies(&self, score: i32) -> bool {
+        score > 0 && (self.scores.len() < TABLE_SIZE || self.scores.iter().any(|entry| score > entry.score))
+    }
+
+    // add a score in its place, dropping the ones pushed out of the table
+    // -> its rank (0 = best), if it stays in
+    pub fn insert(&mut self, entry: HighScore) -> Option<usize> {
+        // ties go to the quicker round, then to the one played first
+        let rank = self.scores.iter()
+            .position(|other| (entry.score, -entry.duration) > (other.score, -other.duration))
+            .unwrap_or(self.scores.len());
+        self.scores.insert(rank, entry);
+        self.scores.truncate(TABLE_SIZE);
+        if rank < TABLE_SIZE {
+            Some(rank)
+        } else {
+            None
+        }
+    }
+}
+
+impl HighScore {
+    // a score played just now
+    pub fn new(name: &str, score: i32, duration: f64, seed: u64) -> HighScore {
+        HighScore {
+            name: name.to_owned(),
+            score,
+            duration,
+            seed,
+            date: now()
+        }
+    }
+
+    // one line of the table, e.g. "ABC   12  1:05  seed 42  2024-05-01"
+    pub fn line(&self) -> String {
+        let seconds = self.duration.max(0.0).round() as u64;
+        let day = self.date.split_whitespace().next().unwrap_or("");
+        format!(
+            "{:<3} {:>4} {:>3}:{:02}  seed {}  {}",
+            self.name, self.score, seconds / 60, seconds % 60, self.seed, day
+        )
+    }
+}
+
+// current date & time (UTC) from the clock
+fn now() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0);
+    let (days, minutes) = (seconds / 86400, seconds % 86400 / 60);
+
+    // days since 1970-01-01 -> year, month & day (civil calendar)
+    let days = days as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02} {:02}:{:02} UTC", year, month, day, minutes / 60, minutes % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, score: i32, duration: f64) -> HighScore {
+        HighScore::new(name, score, duration, 0)
+    }
+
+    #[test]
+    fn scores_go_in_their_place() {
+        let mut table = HighScores::default();
+        assert_eq!(table.insert(entry("AAA", 5, 60.0)), Some(0));
+        assert_eq!(table.insert(entry("BBB", 9, 60.0)), Some(0));
+        assert_eq!(table.insert(entry("CCC", 7, 60.0)), Some(1));
+        // ties go to the quicker round, then to the one played first
+        assert_eq!(table.insert(entry("DDD", 7, 30.0)), Some(1));
+        assert_eq!(table.insert(entry("EEE", 7, 30.0)), Some(2));
+        let names: Vec<&str> = table.scores.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, ["BBB", "DDD", "EEE", "CCC", "AAA"]);
+    }
+
+    #[test]
+    fn the_table_keeps_the_best_ones() {
+        let mut table = HighScores::default();
+        for score in 1..=TABLE_SIZE as i32 {
+            assert!(table.qualifies(score));
+            table.insert(entry("AAA", score, 60.0));
+        }
+        assert!(!table.qualifies(0));
+        assert!(!table.qualifies(1));
+        assert!(table.qualifies(2));
+        assert_eq!(table.insert(entry("BBB", 1, 90.0)), None);
+        assert_eq!(table.insert(entry("CCC", 20, 10.0)), Some(0));
+        assert_eq!(table.scores.len(), TABLE_SIZE);
+        assert_eq!(table.scores.last().unwrap().score, 2);
+    }
+}
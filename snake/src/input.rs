@@ -0,0 +1,99 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::objects::{Direction, Side};
+
+// everything a player can ask the game to do
+// front ends translate their own key events into these
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Input {
+    // steer the snake (of player one, when there are two)
+    Snake(Direction),
+    // steer the snake of player two
+    SecondSnake(Direction),
+
+    // control of the paddle on a side
+    // (on the top & bottom, up moves it left & down moves it right)
+    PaddleUp(Side),
+    PaddleDown(Side),
+
+    // a paddle key going down & up, from front ends that see both
+    // (the paddle moves once right away, then keeps moving while the key is held)
+    HoldUp(Side),
+    HoldDown(Side),
+    Release(Side),
+
+    // start a new round (at any time, e.g. from the pause menu or the game over screen)
+    Restart
+}
+
+// what a player does on the menus
+// (front ends translate their key events into these too, see keys::menu_input_for_key)
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MenuInput {
+    Up,
+    Down,
+    Left,
+    Right,
+    Select,
+    Back
+}
+
+// every input with its name, used by the text formats (e.g. replays)
+const NAMES: [(Input, &str); 29] = [
+    (Input::Snake(Direction::Up), "snake-up"),
+    (Input::Snake(Direction::Down), "snake-down"),
+    (Input::Snake(Direction::Left), "snake-left"),
+    (Input::Snake(Direction::Right), "snake-right"),
+    (Input::PaddleUp(Side::Left), "left-paddle-up"),
+    (Input::PaddleDown(Side::Left), "left-paddle-down"),
+    (Input::PaddleUp(Side::Right), "right-paddle-up"),
+    (Input::PaddleDown(Side::Right), "right-paddle-down"),
+    (Input::PaddleUp(Side::Top), "top-paddle-up"),
+    (Input::PaddleDown(Side::Top), "top-paddle-down"),
+    (Input::PaddleUp(Side::Bottom), "bottom-paddle-up"),
+    (Input::PaddleDown(Side::Bottom), "bottom-paddle-down"),
+    (Input::Restart, "restart"),
+    (Input::HoldUp(Side::Left), "left-paddle-hold-up"),
+    (Input::HoldDown(Side::Left), "left-paddle-hold-down"),
+    (Input::Release(Side::Left), "left-paddle-release"),
+    (Input::HoldUp(Side::Right), "right-paddle-hold-up"),
+    (Input::HoldDown(Side::Right), "right-paddle-hold-down"),
+    (Input::Release(Side::Right), "right-paddle-release"),
+    (Input::HoldUp(Side::Top), "top-paddle-hold-up"),
+    (Input::HoldDown(Side::Top), "top-paddle-hold-down"),
+    (Input::Release(Side::Top), "top-paddle-release"),
+    (Input::HoldUp(Side::Bottom), "bottom-paddle-hold-up"),
+    (Input::HoldDown(Side::Bottom), "bottom-paddle-hold-down"),
+    (Input::Release(Side::Bottom), "bottom-paddle-release"),
+    (Input::SecondSnake(Direction::Up), "second-snake-up"),
+    (Input::SecondSnake(Direction::Down), "second-snake-down"),
+    (Input::SecondSnake(Direction::Left), "second-snake-left"),
+    (Input::SecondSnake(Direction::Right), "second-snake-right")
+];
+
+impl Input {
+    // every input, in the order of their names
+    pub fn all() -> impl Iterator<Item = Input> {
+        NAMES.iter().map(|(input, _)| *input)
+    }
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (_, name) = NAMES.iter().find(|(input, _)| input == self).unwrap();
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Input {
+    type Err = String;
+
+    // read an input back from its name
+    fn from_str(s: &str) -> Result<Input, String> {
+        NAMES.iter()
+            .find(|(_, name)| *name == s)
+            .map(|(input, _)| *input)
+            .ok_or_else(|| format!("unknown input '{}'", s))
+    }
+}
@@ -0,0 +1,271 @@
+// which key does what, kept in the user's config directory so that it can be edited
+// (e.g. ~/.config/snake/keys.toml on Linux)
+//
+// file format (TOML, one line per control, missing controls keep their default keys):
+//   snake-up = ["Up"]
+//   left-paddle-up = ["Q"]
+//   pause = ["P", "Esc"]
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::input::Input;
+use crate::keys::Key;
+use crate::objects::{Direction, Side};
+
+// something a key can be bound to
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Control {
+    // an input for the game
+    Play(Input),
+    // open the pause menu
+    Pause,
+    // save the match as it is, or go back to the last match saved
+    QuickSave,
+    QuickLoad
+}
+
+// every control, in the order of the file & the key bindings screen
+const CONTROLS: [Control; 19] = [
+    Control::Play(Input::Snake(Direction::Up)),
+    Control::Play(Input::Snake(Direction::Down)),
+    Control::Play(Input::Snake(Direction::Left)),
+    Control::Play(Input::Snake(Direction::Right)),
+    Control::Play(Input::SecondSnake(Direction::Up)),
+    Control::Play(Input::SecondSnake(Direction::Down)),
+    Control::Play(Input::SecondSnake(Direction::Left)),
+    Control::Play(Input::SecondSnake(Direction::Right)),
+    Control::Play(Input::PaddleUp(Side::Left)),
+    Control::Play(Input::PaddleDown(Side::Left)),
+    Control::Play(Input::PaddleUp(Side::Right)),
+    Control::Play(Input::PaddleDown(Side::Right)),
+    Control::Play(Input::PaddleUp(Side::Top)),
+    Control::Play(Input::PaddleDown(Side::Top)),
+    Control::Play(Input::PaddleUp(Side::Bottom)),
+    Control::Play(Input::PaddleDown(Side::Bottom)),
+    Control::Pause,
+    Control::QuickSave,
+    Control::QuickLoad
+];
+
+// the keys of every control, in the order of CONTROLS
+// (a key is bound to one control at most)
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyMap {
+    keys: Vec<Vec<Key>>
+}
+
+// arrows steer the snake, I/J/K/L the second snake, letters move the paddles, P & Esc pause
+// -> Q/A left, R/F right, Z/X top, N/M bottom; 5 quick-saves & 9 quick-loads
+impl Default for KeyMap {
+    fn default() -> KeyMap {
+        KeyMap {
+            keys: CONTROLS.iter().map(|control| control.default_keys()).collect()
+        }
+    }
+}
+
+// control design
+impl Control {
+    // every control, in the order of the key bindings screen
+    pub fn all() -> impl Iterator<Item = Control> {
+        CONTROLS.iter().copied()
+    }
+
+    fn default_keys(&self) -> Vec<Key> {
+        let keys: &[Key] = match *self {
+            Control::Play(Input::Snake(Direction::Up)) => &[Key::Up],
+            Control::Play(Input::Snake(Direction::Down)) => &[Key::Down],
+            Control::Play(Input::Snake(Direction::Left)) => &[Key::Left],
+            Control::Play(Input::Snake(Direction::Right)) => &[Key::Right],
+            Control::Play(Input::SecondSnake(Direction::Up)) => &[Key::Char('i')],
+            Control::Play(Input::SecondSnake(Direction::Down)) => &[Key::Char('k')],
+            Control::Play(Input::SecondSnake(Direction::Left)) => &[Key::Char('j')],
+            Control::Play(Input::SecondSnake(Direction::Right)) => &[Key::Char('l')],
+            Control::Play(Input::PaddleUp(Side::Left)) => &[Key::Char('q')],
+            Control::Play(Input::PaddleDown(Side::Left)) => &[Key::Char('a')],
+            Control::Play(Input::PaddleUp(Side::Right)) => &[Key::Char('r')],
+            Control::Play(Input::PaddleDown(Side::Right)) => &[Key::Char('f')],
+            Control::Play(Input::PaddleUp(Side::Top)) => &[Key::Char('z')],
+            Control::Play(Input::PaddleDown(Side::Top)) => &[Key::Char('x')],
+            Control::Play(Input::PaddleUp(Side::Bottom)) => &[Key::Char('n')],
+            Control::Play(Input::PaddleDown(Side::Bottom)) => &[Key::Char('m')],
+            // (restarts come from the menus, holds from the paddle keys)
+            Control::Play(_) => &[],
+            Control::Pause => &[Key::Char('p'), Key::Escape],
+            Control::QuickSave => &[Key::Char('5')],
+            Control::QuickLoad => &[Key::Char('9')]
+        };
+        keys.to_vec()
+    }
+
+    // place in CONTROLS
+    fn index(&self) -> usize {
+        CONTROLS.iter().position(|control| control == self).unwrap()
+    }
+}
+
+impl fmt::Display for Control {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Control::Play(input) => write!(f, "{}", input),
+            Control::Pause => f.write_str("pause"),
+            Control::QuickSave => f.write_str("quick-save"),
+            Control::QuickLoad => f.write_str("quick-load")
+        }
+    }
+}
+
+impl FromStr for Control {
+    type Err = String;
+
+    // read a control back from its name, as in the file
+    fn from_str(s: &str) -> Result<Control, String> {
+        Control::all()
+            .find(|control| control.to_string() == s)
+            .ok_or_else(|| format!("unknown control '{}'", s))
+    }
+}
+
+// key map design
+impl KeyMap {
+    // where the key map lives, if the system has a config directory
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("snake").join("keys.toml"))
+    }
+
+    // read the key map (the default one if there is no file yet)
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<KeyMap> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(KeyMap::default()),
+            Err(err) => return Err(err)
+        };
+        KeyMap::parse(&text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    // write the key map, creating its directory if needed
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_toml())
+    }
+
+    // read a key map from its text
+    pub fn parse(text: &str) -> Result<KeyMap, String> {
+        let table: BTreeMap<String, Vec<String>> = toml::from_str(text).map_err(|err| err.to_string())?;
+
+        let mut key_map = KeyMap::default();
+        for (name, keys) in table {
+            let control: Control = name.parse()?;
+            let keys = keys.iter()
+                .map(|key| key.parse())
+                .collect::<Result<Vec<Key>, String>>()
+                .map_err(|err| format!("{}: {}", name, err))?;
+            key_map.keys[control.index()] = keys;
+        }
+        key_map.check()?;
+        Ok(key_map)
+    }
+
+    // key map as TOML text, every control in order
+    pub fn to_toml(&self) -> String {
+        let mut text = String::from("# keys of every control, e.g. snake-up = [\"Up\"] or pause = [\"P\", \"Esc\"]\n");
+        for (control, keys) in CONTROLS.iter().zip(&self.keys) {
+            let keys: Vec<String> = keys.iter().map(|key| format!("\"{}\"", key)).collect();
+            text.push_str(&format!("{} = [{}]\n", control, keys.join(", ")));
+        }
+        text
+    }
+
+    // the control a key is bound to, if any
+    pub fn control(&self, key: Key) -> Option<Control> {
+        let key = key.lowercase();
+        CONTROLS.iter()
+            .zip(&self.keys)
+            .find(|(_, keys)| keys.contains(&key))
+            .map(|(control, _)| *control)
+    }
+
+    // the input a key gives the game, if any
+    pub fn input(&self, key: Key) -> Option<Input> {
+        match self.control(key) {
+            Some(Control::Play(input)) => Some(input),
+            _ => None
+        }
+    }
+
+    // true if the key opens the pause menu
+    pub fn pauses(&self, key: Key) -> bool {
+        self.control(key) == Some(Control::Pause)
+    }
+
+    // the keys bound to a control
+    pub fn keys(&self, control: Control) -> &[Key] {
+        &self.keys[control.index()]
+    }
+
+    // bind a control to this key alone
+    // -> refused if another control has the key already
+    pub fn bind(&mut self, control: Control, key: Key) -> Result<(), String> {
+        let key = key.lowercase();
+        match self.control(key) {
+            Some(other) if other != control => Err(format!("{} is already bound to {}", key, other)),
+            _ => {
+                self.keys[control.index()] = vec![key];
+                Ok(())
+            }
+        }
+    }
+
+    // check that no key is bound to two controls
+    fn check(&self) -> Result<(), String> {
+        for (index, keys) in self.keys.iter().enumerate() {
+            for key in keys {
+                let other = self.keys[..index].iter().position(|other| other.contains(key));
+                if let Some(other) = other {
+                    return Err(format!("{} is bound to both {} and {}", key, CONTROLS[other], CONTROLS[index]));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_keys_do_not_clash() {
+        let key_map = KeyMap::default();
+        assert!(key_map.check().is_ok());
+        assert_eq!(key_map.input(Key::Char('Q')), Some(Input::PaddleUp(Side::Left)));
+        assert!(key_map.pauses(Key::Escape));
+    }
+
+    #[test]
+    fn toml_round_trip() {
+        let mut key_map = KeyMap::default();
+        key_map.bind(Control::Play(Input::Snake(Direction::Up)), Key::Char('w')).unwrap();
+        assert_eq!(KeyMap::parse(&key_map.to_toml()).unwrap(), key_map);
+    }
+
+    #[test]
+    fn a_key_is_bound_to_one_control() {
+        let mut key_map = KeyMap::default();
+        let err = key_map.bind(Control::Pause, Key::Char('q')).unwrap_err();
+        assert!(err.contains("already bound to left-paddle-up"));
+        // (binding a control to its own key again is fine)
+        assert!(key_map.bind(Control::Play(Input::PaddleUp(Side::Left)), Key::Char('Q')).is_ok());
+
+        let err = KeyMap::parse("pause = [\"Up\"]").unwrap_err();
+        assert!(err.contains("bound to both snake-up and pause"));
+        assert!(KeyMap::parse("jump = [\"Up\"]").unwrap_err().contains("unknown control"));
+    }
+}
@@ -0,0 +1,78 @@
+// keys every front end understands
+// front ends turn their own key events into these, so the controls are the same everywhere
+// (which input each of them gives is up to the key map, see keymap.rs)
+use std::fmt;
+use std::str::FromStr;
+
+use crate::input::MenuInput;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Backspace,
+    Escape,
+    // letters & digits, lowercase
+    Char(char)
+}
+
+// arrows move through the menus, Enter picks & Esc goes back
+// (so the menus answer the same keys in every front end)
+pub fn menu_input_for_key(key: Key) -> Option<MenuInput> {
+    match key {
+        Key::Up => Some(MenuInput::Up),
+        Key::Down => Some(MenuInput::Down),
+        Key::Left => Some(MenuInput::Left),
+        Key::Right => Some(MenuInput::Right),
+        Key::Enter => Some(MenuInput::Select),
+        Key::Escape => Some(MenuInput::Back),
+        Key::Backspace | Key::Char(_) => None
+    }
+}
+
+// key design
+impl Key {
+    // letters as front ends & key maps keep them
+    pub fn lowercase(&self) -> Key {
+        match *self {
+            Key::Char(c) => Key::Char(c.to_ascii_lowercase()),
+            key => key
+        }
+    }
+}
+
+// name of the key, as the key bindings screen & the key map file show it
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Key::Up => f.write_str("Up"),
+            Key::Down => f.write_str("Down"),
+            Key::Left => f.write_str("Left"),
+            Key::Right => f.write_str("Right"),
+            Key::Enter => f.write_str("Enter"),
+            Key::Backspace => f.write_str("Backspace"),
+            Key::Escape => f.write_str("Esc"),
+            Key::Char(c) => write!(f, "{}", c.to_ascii_uppercase())
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = String;
+
+    // read a key back from its name (letters in either case)
+    fn from_str(s: &str) -> Result<Key, String> {
+        let named = [Key::Up, Key::Down, Key::Left, Key::Right, Key::Enter, Key::Backspace, Key::Escape];
+        if let Some(key) = named.iter().find(|key| key.to_string().eq_ignore_ascii_case(s)) {
+            return Ok(*key);
+        }
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii_alphanumeric() => Ok(Key::Char(c.to_ascii_lowercase())),
+            _ => Err(format!("unknown key '{}' (a letter, a digit, Up, Down, Left, Right, Enter, Backspace or Esc)", s))
+        }
+    }
+}
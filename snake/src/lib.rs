@@ -0,0 +1,50 @@
+// snake & pong rules without any window or graphics
+// front ends (the piston window & the terminal in main.rs) feed inputs in and draw the view
+extern crate rand;
+extern crate rand_pcg;
+extern crate serde;
+extern crate toml;
+extern crate serde_json;
+extern crate bincode;
+extern crate png;
+extern crate gif;
+extern crate dirs;
+
+pub mod objects;
+pub mod input;
+pub mod keys;
+pub mod keymap;
+pub mod view;
+pub mod settings;
+pub mod state;
+pub mod game;
+pub mod replay;
+pub mod controller;
+pub mod paddle_ai;
+pub mod autopilot;
+pub mod env;
+pub mod raster;
+pub mod highscores;
+pub mod net;
+pub mod link;
+pub mod rollback;
+pub mod save;
+pub mod bot;
+pub mod tournament;
+
+pub use crate::autopilot::Autopilot;
+pub use crate::bot::Bot;
+pub use crate::controller::Controller;
+pub use crate::env::{Action, Encoding, Env, EnvSettings, Info, Observation, Rewards};
+pub use crate::game::Game;
+pub use crate::highscores::{HighScore, HighScores};
+pub use crate::input::Input;
+pub use crate::keymap::KeyMap;
+pub use crate::raster::{Animation, Image};
+pub use crate::objects::{Direction, Side};
+pub use crate::paddle_ai::{Difficulty, PaddleAi};
+pub use crate::replay::Replay;
+pub use crate::settings::Settings;
+pub use crate::state::State;
+pub use crate::tournament::{Agent, Tournament, TournamentSettings};
+pub use crate::view::{Motion, View};
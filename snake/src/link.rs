@@ -0,0 +1,244 @@
+// packets between two peers: over UDP, in memory (both peers in one process), or through
+// a simulated network that delays, shuffles & drops them
+// packets may get lost or come out of order, whoever uses a link has to live with it
+use std::collections::VecDeque;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::str::FromStr;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+
+// biggest packet a link carries
+const MAX_PACKET: usize = 65507;
+
+// a way to the other peer
+// (now is the time in seconds on the caller's clock, for links that go by it)
+pub trait Transport: Send {
+    // send a packet, if the link can
+    fn send(&mut self, packet: &[u8], now: f64);
+
+    // every packet arrived since the last call
+    fn receive(&mut self, now: f64) -> Vec<Vec<u8>>;
+}
+
+// packets over a UDP socket
+pub struct UdpTransport {
+    socket: UdpSocket,
+    // the other peer, once known (a peer waiting to be joined learns it from the first packet)
+    peer: Option<SocketAddr>
+}
+
+// packets handed over in memory, see local_pair
+pub struct LocalTransport {
+    sender: Sender<Vec<u8>>,
+    receiver: Receiver<Vec<u8>>
+}
+
+// how bad the simulated network is
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Conditions {
+    // seconds a packet takes on average
+    pub latency: f64,
+    // seconds it may take more or less than that, at random
+    // (so that packets overtake each other)
+    pub jitter: f64,
+    // share of packets lost, from 0 to 1
+    pub loss: f64
+}
+
+// a link through a simulated network: what is sent waits out its delay before it goes
+pub struct Simulated<T: Transport> {
+    inner: T,
+    conditions: Conditions,
+    rng: Pcg32,
+    // packets on their way, with the time they arrive
+    in_flight: VecDeque<(f64, Vec<u8>)>
+}
+
+// udp design
+impl UdpTransport {
+    // wait for a peer on an address, e.g. "0.0.0.0:7777"
+    pub fn bind<A: ToSocketAddrs>(address: A) -> io::Result<UdpTransport> {
+        let socket = UdpSocket::bind(address)?;
+        socket.set_nonblocking(true)?;
+        Ok(UdpTransport { socket, peer: None })
+    }
+
+    // talk to the peer on an address, from any local port
+    pub fn connect<A: ToSocketAddrs>(address: A) -> io::Result<UdpTransport> {
+        let peer = address.to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no address to connect to"))?;
+        let local = if peer.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+        let socket = UdpSocket::bind(local)?;
+        socket.set_nonblocking(true)?;
+        Ok(UdpTransport { socket, peer: Some(peer) })
+    }
+
+    // the address it is bound to
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+}
+
+impl Transport for UdpTransport {
+    fn send(&mut self, packet: &[u8], _now: f64) {
+        if let Some(peer) = self.peer {
+            // (a packet the system cannot send is as good as lost)
+            let _ = self.socket.send_to(packet, peer);
+        }
+    }
+
+    fn receive(&mut self, _now: f64) -> Vec<Vec<u8>> {
+        let mut packets = Vec::new();
+        let mut buffer = vec![0; MAX_PACKET];
+        loop {
+            match self.socket.recv_from(&mut buffer) {
+                Ok((size, from)) => {
+                    // packets from anyone but the peer are left alone
+                    if *self.peer.get_or_insert(from) == from {
+                        packets.push(buffer[..size].to_vec());
+                    }
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                // (nothing more to read, or an error the next packet may not have)
+                Err(_) => break
+            }
+        }
+        packets
+    }
+}
+
+// two ends of a link in memory, one per peer
+pub fn local_pair() -> (LocalTransport, LocalTransport) {
+    let (first_sender, second_receiver) = channel();
+    let (second_sender, first_receiver) = channel();
+    (
+        LocalTransport { sender: first_sender, receiver: first_receiver },
+        LocalTransport { sender: second_sender, receiver: second_receiver }
+    )
+}
+
+impl Transport for LocalTransport {
+    fn send(&mut self, packet: &[u8], _now: f64) {
+        // (the other end may be gone already)
+        let _ = self.sender.send(packet.to_vec());
+    }
+
+    fn receive(&mut self, _now: f64) -> Vec<Vec<u8>> {
+        self.receiver.try_iter().collect()
+    }
+}
+
+impl Transport for Box<dyn Transport> {
+    fn send(&mut self, packet: &[u8], now: f64) {
+        (**self).send(packet, now);
+    }
+
+    fn receive(&mut self, now: f64) -> Vec<Vec<u8>> {
+        (**self).receive(now)
+    }
+}
+
+impl FromStr for Conditions {
+    type Err = String;
+
+    // read conditions as "latency,jitter,loss" in milliseconds & percent, e.g. "80,20,5"
+    fn from_str(s: &str) -> Result<Conditions, String> {
+        let numbers = s.split(',')
+            .map(|part| part.trim().parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()
+            .map_err(|_| format!("invalid network conditions '{}' (latency,jitter,loss e.g. 80,20,5)", s))?;
+        let (latency, jitter, loss) = match numbers.as_slice() {
+            [latency, jitter, loss] => (*latency, *jitter, *loss),
+            _ => return Err(format!("network conditions need 3 numbers: latency,jitter,loss (got '{}')", s))
+        };
+        if !(latency >= 0.0 && jitter >= 0.0 && (0.0..=100.0).contains(&loss)) {
+            return Err(format!("network conditions out of range: '{}' (loss between 0 and 100 percent)", s));
+        }
+        Ok(Conditions {
+            latency: latency / 1000.0,
+            jitter: jitter / 1000.0,
+            loss: loss / 100.0
+        })
+    }
+}
+
+// simulated network design
+impl<T: Transport> Simulated<T> {
+    // a link sending through the conditions, drawing its delays & losses from a seed
+    pub fn new(inner: T, conditions: Conditions, seed: u64) -> Simulated<T> {
+        Simulated {
+            inner,
+            conditions,
+            rng: Pcg32::seed_from_u64(seed),
+            in_flight: VecDeque::new()
+        }
+    }
+
+    // hand the packets whose delay is over to the link under it, in the order they arrive
+    fn deliver(&mut self, now: f64) {
+        let (mut due, waiting): (Vec<_>, Vec<_>) = self.in_flight.drain(..).partition(|(arrival, _)| *arrival <= now);
+        self.in_flight = waiting.into();
+        due.sort_by(|(first, _), (second, _)| first.total_cmp(second));
+        for (_, packet) in due {
+            self.inner.send(&packet, now);
+        }
+    }
+}
+
+impl<T: Transport> Transport for Simulated<T> {
+    fn send(&mut self, packet: &[u8], now: f64) {
+        self.deliver(now);
+        if self.rng.gen::<f64>() < self.conditions.loss {
+            return;
+        }
+        let jitter = if self.conditions.jitter > 0.0 {
+            self.rng.gen_range(-self.conditions.jitter, self.conditions.jitter)
+        } else {
+            0.0
+        };
+        let arrival = now + (self.conditions.latency + jitter).max(0.0);
+        self.in_flight.push_back((arrival, packet.to_vec()));
+        self.deliver(now);
+    }
+
+    fn receive(&mut self, now: f64) -> Vec<Vec<u8>> {
+        self.deliver(now);
+        self.inner.receive(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packets_overtake_each_other_as_their_delays_say() {
+        let conditions = Conditions { latency: 0.05, jitter: 0.04, loss: 0.0 };
+        let (near, mut far) = local_pair();
+        let mut link = Simulated::new(near, conditions, 3);
+        for index in 0..50u8 {
+            link.send(&[index], f64::from(index) * 0.001);
+        }
+        // (all of them are due by then, and come out at once)
+        link.receive(1.0);
+        let received: Vec<u8> = far.receive(1.0).into_iter().map(|packet| packet[0]).collect();
+
+        // the delays drawn from the same seed, one loss roll & one jitter per packet
+        let mut rng = Pcg32::seed_from_u64(3);
+        let mut arrivals: Vec<(f64, u8)> = (0..50u8)
+            .map(|index| {
+                rng.gen::<f64>();
+                let jitter = rng.gen_range(-conditions.jitter, conditions.jitter);
+                (f64::from(index) * 0.001 + conditions.latency + jitter, index)
+            })
+            .collect();
+        arrivals.sort_by(|(first, _), (second, _)| first.total_cmp(second));
+        let expected: Vec<u8> = arrivals.into_iter().map(|(_, index)| index).collect();
+        assert_eq!(received, expected);
+        assert_ne!(received, (0..50).collect::<Vec<u8>>());
+    }
+}
@@ -1,107 +1,209 @@
 extern crate piston_window;
-extern crate rand;
 extern crate find_folder;
 extern crate opengl_graphics;
+extern crate crossterm;
+extern crate snake;
 
+mod cli;
+mod compete;
 mod draw;
-mod objects;
-mod game;
+mod export;
+mod session;
+mod tui;
+mod window;
 
-use piston_window::*;
-use piston_window::types::Color;
+use std::process;
 
-use crate::game::Game;
-use crate::draw::to_coord_u32;
-
-const BACK_COLOR: Color = [0.5, 0.5, 0.5, 1.0];
+use snake::link::{Simulated, Transport, UdpTransport};
+use snake::net::{Client, Host};
+use snake::rollback::Rollback;
+use snake::save;
+use snake::{Bot, Replay, Settings};
+use crate::cli::{Command, USAGE};
+use crate::session::Session;
 
 fn main() {
-    // size of the gameboard
-    let (width, height) = (30, 30);
-
-    // open up the gameboard with width & height
-    let mut window: PistonWindow =
-        WindowSettings::new("Snake", [to_coord_u32(width), to_coord_u32(height)])
-            .fullscreen(true)
-            .exit_on_esc(true)
-            .build()
-            .unwrap();
-
-    // create a game
-    let mut game = Game::new(width, height);    
-
-    // load font 
-    let assets = find_folder::Search::ParentsThenKids(3, 3)
-        .for_folder("assets").unwrap();
-    let ref font = assets.join("FiraMono-Bold.ttf");    
-    let mut glyphs = window.load_font(font).unwrap();
-
-    // game end statements
-    let gameover: &str = "GAME OVER";
-    let gamewin: &str = "WIN!";
-
-    // game loop
-    while let Some(e) = window.next() {        
-        // get score & make it a string
-        let score: i32 = game.score();
-        let mut scoreboard = "Score: ".to_owned();
-        let s_score : String = score.to_string().to_owned();    
-        scoreboard.push_str(&s_score);        
-
-        // game end status
-        let gameover_state: bool = game.game_over();
-        let gamewin_state: bool = game.game_win();
-
-        // key inputs
-        if let Some(Button::Keyboard(key)) = e.press_args() {
-            game.key_pressed(key);
-        }        
-
-        // draw game components & texts 
-        window.draw_2d(&e, |c, g, device| {
-            clear(BACK_COLOR, g);
-            game.draw(&c, g);
-
-            let transform = c.transform.trans(900.0, 300.0);
-            let gameover_statement = c.transform.trans(450.0, 400.0);
-            let gamewin_statement = c.transform.trans(450.0, 400.0);
-            
-            // score text
-            text::Text::new_color([0.0, 0.0, 0.0, 1.0], 20).draw(
-                &scoreboard,
-                &mut glyphs,
-                &c.draw_state,
-                transform, g
-            ).unwrap();            
-
-            // game over statement text
-            if gameover_state {
-                text::Text::new_color([0.0, 0.0, 0.0, 1.0], 70).draw(
-                    &gameover,
-                    &mut glyphs,
-                    &c.draw_state,
-                    gameover_statement, g
-                ).unwrap();     
+    let command = Command::parse().unwrap_or_else(|err| {
+        eprintln!("snake: {}", err);
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    });
+
+    // draw a replay into a file, or play a tournament, instead of playing
+    let options = match command {
+        Command::Play(options) => *options,
+        Command::Export(export) => {
+            if let Err(err) = export::run(&export) {
+                eprintln!("snake: cannot export {}: {}", export.replay.display(), err);
+                process::exit(1);
+            }
+            return;
+        }
+        Command::Tournament(tournament) => {
+            if let Err(err) = compete::run(&tournament) {
+                eprintln!("snake: {}", err);
+                process::exit(1);
             }
+            return;
+        }
+    };
+
+    // a recorded match to play back
+    let replay = options.replay.as_ref().map(|path| {
+        Replay::load(path).unwrap_or_else(|err| {
+            eprintln!("snake: cannot read replay {}: {}", path.display(), err);
+            process::exit(1);
+        })
+    });
+
+    // board size, speeds, colors & win condition
+    let settings = match &options.config {
+        Some(path) => Settings::load(path).unwrap_or_else(|err| {
+            eprintln!("snake: {}", err);
+            process::exit(1);
+        }),
+        None => Settings::default()
+    };
+
+    // a rollback match: waiting for the other peer, or joining it
+    let rollback = if options.rollback {
+        Some(start_rollback(&options, &settings))
+    } else {
+        None
+    };
+
+    // a match hosted by another player, to play a part of or to watch
+    let client = options.join.as_ref().filter(|_| !options.rollback).map(|(address, role)| {
+        Client::join(address.as_str(), *role).unwrap_or_else(|err| {
+            eprintln!("snake: cannot join {}: {}", address, err);
+            process::exit(1);
+        })
+    });
+    let client = client.or_else(|| options.watch.as_ref().map(|address| {
+        Client::watch(address.as_str()).unwrap_or_else(|err| {
+            eprintln!("snake: cannot watch {}: {}", address, err);
+            process::exit(1);
+        })
+    }));
+
+    // settings & seed, taken from the replay when playing one back
+    // (or from the host, but with the player's own colors, when joining a match or playing with rollback)
+    let (client, settings, seed) = match (&replay, client, &rollback) {
+        (Some(replay), _, _) => (None, replay.settings.clone(), replay.seed),
+        (None, _, Some(rollback)) => {
+            let colors = settings.colors.clone();
+            (None, Settings { colors, ..rollback.game().settings().clone() }, rollback.game().seed())
+        }
+        (None, Some((client, seed, host_settings)), None) => {
+            let colors = settings.colors.clone();
+            (Some(client), Settings { colors, ..host_settings }, seed)
+        }
+        (None, None, None) => (None, settings, options.seed)
+    };
+
+    // create a game, or play on from a saved one
+    // log the seed so that a match can be played again
+    let mut session = Session::new(&options, &settings, seed, replay.as_ref());
+    if let Some(path) = &options.load {
+        match save::load(path) {
+            Ok(game) => session.resume(game),
+            Err(err) => {
+                eprintln!("snake: cannot load the game from {}: {}", path.display(), err);
+                process::exit(1);
+            }
+        }
+    }
+    eprintln!("seed: {}", session.game.seed());
+
+    // programs playing parts of the match
+    for (role, command) in &options.bots {
+        match Bot::spawn(command, *role, &session.game, options.bot_time) {
+            Ok(bot) => {
+                eprintln!("{} plays the {}", bot.name(), role);
+                session.add_bot(bot);
+            }
+            Err(err) => {
+                eprintln!("snake: cannot start the bot for the {} ({}): {}", role, command, err);
+                process::exit(1);
+            }
+        }
+    }
 
-            // game win statement text
-            if gamewin_state {
-                text::Text::new_color([0.0, 0.0, 0.0, 1.0], 70).draw(
-                    &gamewin,
-                    &mut glyphs,
-                    &c.draw_state,
-                    gamewin_statement, g
-                ).unwrap();     
+    // other players join on the address the host gives, or this one joins them
+    // (spectators of a rollback match watch over TCP on the address its UDP link is on)
+    if let Some(rollback) = rollback {
+        session.play_rollback(rollback);
+    }
+    if let Some(address) = &options.host {
+        match Host::bind(address.as_str()) {
+            Ok(host) => {
+                if let Ok(address) = host.local_addr() {
+                    eprintln!("hosting on {}", address);
+                }
+                session.host(host);
+            }
+            Err(err) => {
+                eprintln!("snake: cannot host on {}: {}", address, err);
+                process::exit(1);
             }
+        }
+    }
+    if let Some(client) = client {
+        session.join(client);
+    }
 
-            // flush so that the string buffer can be passed to GPU completely
-            glyphs.factory.encoder.flush(device);
-        });
+    // animation of the whole match
+    if let Some(path) = &options.gif {
+        if let Err(err) = session.capture_gif(path, cli::GIF_BLOCK) {
+            eprintln!("snake: cannot write {}: {}", path.display(), err);
+            process::exit(1);
+        }
+    }
 
-        // update game status
-        e.update(|arg| {
-            game.update(arg.dt);
-        });
+    // play it in the terminal or in a window
+    if options.tui {
+        if let Err(err) = tui::run(&mut session, &settings.colors) {
+            eprintln!("snake: terminal error: {}", err);
+        }
+    } else {
+        window::run(&mut session, &settings.colors);
+    }
 
+    // save the recording & the animation
+    session.finish(options.record.as_deref());
+}
+
+// the link to the other peer of a rollback match, through the simulated network if asked,
+// and the match on it
+fn start_rollback(options: &cli::Options, settings: &Settings) -> Rollback {
+    let address = match (&options.host, &options.join) {
+        (Some(address), _) => address,
+        (None, Some((address, _))) => address,
+        (None, None) => unreachable!("--rollback goes with --host or --join")
+    };
+    let transport = match &options.join {
+        None => UdpTransport::bind(address.as_str()),
+        Some(_) => UdpTransport::connect(address.as_str())
+    };
+    let transport = transport.unwrap_or_else(|err| {
+        eprintln!("snake: cannot open {}: {}", address, err);
+        process::exit(1);
+    });
+    if options.join.is_none() {
+        if let Ok(address) = transport.local_addr() {
+            eprintln!("waiting for the other player on {}", address);
+        }
+    }
+    let link: Box<dyn Transport> = match options.netsim {
+        Some(conditions) => Box::new(Simulated::new(transport, conditions, options.seed)),
+        None => Box::new(transport)
+    };
+
+    match &options.join {
+        Some((address, role)) => Rollback::join(link, *role).unwrap_or_else(|err| {
+            eprintln!("snake: cannot join {}: {}", address, err);
+            process::exit(1);
+        }),
+        None => Rollback::host(link, settings, options.seed)
     }
-}
\ No newline at end of file
+}
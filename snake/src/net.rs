@@ -0,0 +1,693 @@
+// playing a match over the network: one player hosts it, the others join it & play a part of it
+// (the snake, the second snake or a paddle), or only watch it
+// -> the host runs the only Game: clients send the inputs of their part, and the host sends every
+//    client what the board looks like whenever it changes, so nobody can disagree on the match
+//    (a client coming in late gets the last frame right after the welcome, to catch up)
+// -> how far the clock is towards the next tick is not sent (it changes on every frame):
+//    clients work it out from the time since the last tick came in
+//
+// newline-delimited JSON over TCP, e.g. on localhost port 7777:
+//   client -> host   {"Join":{"role":"left-paddle"}} or "Watch"       first, once
+//                    {"Input":{"input":"left-paddle-hold-up"}}        inputs of its part only
+//   host -> client   {"Welcome":{"seed":42,"settings":{...}}}         or {"Refused":{"reason":"..."}}
+//                    {"Frame":{"view":{...},"motion":{...},"status":"Starting in 3"}}
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::game::Game;
+use crate::input::Input;
+use crate::objects::Side;
+use crate::settings::Settings;
+use crate::state::State;
+use crate::view::{Motion, View};
+
+// port a host listens on when none is given
+pub const DEFAULT_PORT: u16 = 7777;
+
+// seconds a client waits for the host to let it in
+const JOIN_TIMEOUT: f64 = 5.0;
+
+// bytes waiting to be sent before the other end counts as gone (a few seconds of frames)
+const MAX_BACKLOG: usize = 1 << 20;
+
+// longest line taken from the other end (a frame of the biggest board fits), and the bytes
+// read at most per poll, so that a flood of lines does not hold up the match
+const MAX_LINE: usize = 1 << 20;
+
+// connections still saying which part they want, and the time they have to say it
+const MAX_PENDING: usize = 16;
+const HELLO_TIMEOUT: Duration = Duration::from_secs(5);
+
+// the part of the match a player plays
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Role {
+    Snake,
+    SecondSnake,
+    Paddle(Side)
+}
+
+// what a client tells the host
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ClientMessage {
+    Join { role: String },
+    // only see the match, without a part
+    Watch,
+    Input { input: String }
+}
+
+// what the host tells a client
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum HostMessage {
+    Welcome { seed: u64, settings: Settings },
+    Refused { reason: String },
+    Frame(Frame)
+}
+
+// the board as the host shows it, with a word on where the match is at
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Frame {
+    pub view: View,
+    pub motion: Motion,
+    pub status: String
+}
+
+// what happened on the host since it was last asked
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Event {
+    Joined(Role),
+    Left(Role),
+    // a spectator came in or went away
+    Watching,
+    StoppedWatching,
+    // an input of a remote player, for the part it plays
+    Input(Input)
+}
+
+// the host's end: remote players, spectators & the connections still saying which part they want
+pub struct Host {
+    listener: TcpListener,
+    // with the time they came in
+    pending: Vec<(Instant, Connection)>,
+    players: Vec<(Role, Connection)>,
+    spectators: Vec<Connection>,
+    // the frame sent last, for those coming in late
+    last: Option<Frame>
+}
+
+// a player's end joined to a host, or a spectator's
+pub struct Client {
+    connection: Connection,
+    // the part it plays, none for a spectator
+    role: Option<Role>,
+    // the frame that came with the welcome, until a newer one does
+    frame: Option<Frame>
+}
+
+// lines going both ways over a stream, without ever waiting on it
+struct Connection {
+    stream: TcpStream,
+    incoming: Vec<u8>,
+    outgoing: Vec<u8>
+}
+
+// role design
+impl Role {
+    // true if the input is one of the part
+    pub fn owns(&self, input: Input) -> bool {
+        match (*self, input) {
+            (Role::Snake, Input::Snake(_)) => true,
+            (Role::SecondSnake, Input::SecondSnake(_)) => true,
+            (Role::Paddle(side), Input::PaddleUp(other))
+            | (Role::Paddle(side), Input::PaddleDown(other))
+            | (Role::Paddle(side), Input::HoldUp(other))
+            | (Role::Paddle(side), Input::HoldDown(other))
+            | (Role::Paddle(side), Input::Release(other)) => side == other,
+            _ => false
+        }
+    }
+
+    // the part an input is of, if a player can play it
+    pub fn of(input: Input) -> Option<Role> {
+        match input {
+            Input::Snake(_) => Some(Role::Snake),
+            Input::SecondSnake(_) => Some(Role::SecondSnake),
+            Input::PaddleUp(side) | Input::PaddleDown(side) => Some(Role::Paddle(side)),
+            Input::HoldUp(side) | Input::HoldDown(side) | Input::Release(side) => Some(Role::Paddle(side)),
+            Input::Restart => None
+        }
+    }
+
+    // true if the part is on the board of a game
+    pub fn in_game(&self, game: &Game) -> bool {
+        match *self {
+            Role::Snake => true,
+            Role::SecondSnake => game.snakes().count() > 1,
+            Role::Paddle(side) => game.paddle(side).is_some()
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Role::Snake => f.write_str("snake"),
+            Role::SecondSnake => f.write_str("second-snake"),
+            Role::Paddle(side) => write!(f, "{}-paddle", format!("{:?}", side).to_lowercase())
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    // read a role from its name, e.g. "snake" or "left-paddle"
+    fn from_str(s: &str) -> Result<Role, String> {
+        match s {
+            "snake" => Ok(Role::Snake),
+            "second-snake" => Ok(Role::SecondSnake),
+            _ => match s.strip_suffix("-paddle").map(str::parse) {
+                Some(Ok(side)) => Ok(Role::Paddle(side)),
+                _ => Err(format!(
+                    "unknown role '{}' (snake, second-snake, left-paddle, right-paddle, top-paddle or bottom-paddle)",
+                    s
+                ))
+            }
+        }
+    }
+}
+
+// frame design
+impl Frame {
+    // the board of a game & the screen it is on
+    pub fn new(game: &Game) -> Frame {
+        Frame {
+            view: game.view(),
+            motion: game.motion(),
+            status: status(game.state())
+        }
+    }
+}
+
+// where the host's match is at, as its players see it
+fn status(state: &State) -> String {
+    match state {
+        State::Title(_) | State::Controls { .. } | State::Rebinding(_) | State::HighScores => {
+            "Waiting for the host to start".to_owned()
+        }
+        State::Countdown(left) => format!("Starting in {}", left.ceil().max(1.0)),
+        State::Playing => String::new(),
+        State::Paused(_) => "Paused by the host".to_owned(),
+        State::GameOver | State::Win | State::HighScoreEntry { .. } => "Round over, the host starts the next one".to_owned(),
+        State::Quit => "The host left".to_owned()
+    }
+}
+
+// host design
+impl Host {
+    // listen for players on an address, e.g. "0.0.0.0:7777" for the whole LAN
+    pub fn bind<A: ToSocketAddrs>(address: A) -> io::Result<Host> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        Ok(Host {
+            listener,
+            pending: Vec::new(),
+            players: Vec::new(),
+            spectators: Vec::new(),
+            last: None
+        })
+    }
+
+    // the address it listens on (with the port picked by the system, if it was 0)
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    // the parts remote players play
+    pub fn roles(&self) -> impl Iterator<Item = Role> + '_ {
+        self.players.iter().map(|(role, _)| *role)
+    }
+
+    // how many spectators watch the match
+    pub fn spectators(&self) -> usize {
+        self.spectators.len()
+    }
+
+    // let new players & spectators in, and read what the players sent
+    // -> a part is refused if it is not on the board, already taken, or kept by the host
+    //    (e.g. because the computer plays it); spectators are always let in
+    pub fn poll<F: Fn(Role) -> bool>(&mut self, game: &Game, kept: F) -> Vec<Event> {
+        let mut events = Vec::new();
+
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    // (too many at once are turned away, the stream closing as it is dropped)
+                    if self.pending.len() >= MAX_PENDING {
+                        continue;
+                    }
+                    if let Ok(connection) = Connection::new(stream) {
+                        self.pending.push((Instant::now(), connection));
+                    }
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_) => break
+            }
+        }
+
+        // the first message of a connection says which part it wants
+        // (one that does not say in time is dropped, what came along with it is read as well)
+        for (since, mut connection) in std::mem::take(&mut self.pending) {
+            let mut messages = match connection.receive::<ClientMessage>() {
+                Ok(messages) => messages.into_iter(),
+                Err(_) => continue
+            };
+            let role = match messages.next() {
+                Some(ClientMessage::Join { role }) => role,
+                Some(ClientMessage::Watch) => {
+                    if self.welcome(&mut connection, game).is_ok() {
+                        self.spectators.push(connection);
+                        events.push(Event::Watching);
+                    }
+                    continue;
+                }
+                Some(ClientMessage::Input { .. }) => {
+                    let reason = "join first".to_owned();
+                    let _ = connection.send(&HostMessage::Refused { reason });
+                    continue;
+                }
+                None => {
+                    if since.elapsed() < HELLO_TIMEOUT {
+                        self.pending.push((since, connection));
+                    }
+                    continue;
+                }
+            };
+            let answer = match role.parse::<Role>() {
+                Ok(role) if !role.in_game(game) => Err(format!("there is no {} in this match", role)),
+                Ok(role) if kept(role) || self.roles().any(|other| other == role) => {
+                    Err(format!("{} is taken", role))
+                }
+                Ok(role) => Ok(role),
+                Err(err) => Err(err)
+            };
+            match answer {
+                Ok(role) => {
+                    if self.welcome(&mut connection, game).is_ok() {
+                        self.players.push((role, connection));
+                        events.push(Event::Joined(role));
+                        events.extend(inputs(role, messages));
+                    }
+                }
+                Err(reason) => {
+                    let _ = connection.send(&HostMessage::Refused { reason });
+                }
+            }
+        }
+
+        // inputs of the players' parts, in the order they came
+        let mut left = Vec::new();
+        for (index, (role, connection)) in self.players.iter_mut().enumerate() {
+            match connection.receive::<ClientMessage>() {
+                Ok(messages) => events.extend(inputs(*role, messages)),
+                Err(_) => left.push(index)
+            }
+        }
+        for index in left.into_iter().rev() {
+            let (role, _) = self.players.remove(index);
+            events.push(Event::Left(role));
+        }
+
+        // spectators only have to be seen going away (whatever they send is not listened to)
+        let watching = self.spectators.len();
+        self.spectators.retain_mut(|connection| connection.receive::<ClientMessage>().is_ok());
+        events.extend((self.spectators.len()..watching).map(|_| Event::StoppedWatching));
+        events
+    }
+
+    // let a client in: the match's seed & settings, then the board as last sent
+    fn welcome(&self, connection: &mut Connection, game: &Game) -> io::Result<()> {
+        connection.send(&HostMessage::Welcome { seed: game.seed(), settings: game.settings().clone() })?;
+        match &self.last {
+            Some(frame) => connection.send(&HostMessage::Frame(frame.clone())),
+            None => Ok(())
+        }
+    }
+
+    // show every player & spectator the board, if it changed since the last frame sent
+    // (one that cannot be reached any more leaves on the next poll, without holding up the others)
+    // -> the progress towards the next tick is left out, so frames go out on ticks & moves only
+    pub fn send(&mut self, frame: &Frame) {
+        let mut frame = frame.clone();
+        frame.motion.progress = 0.0;
+        if self.last.as_ref() == Some(&frame) {
+            return;
+        }
+        let message = HostMessage::Frame(frame.clone());
+        let connections = self.players.iter_mut().map(|(_, connection)| connection).chain(&mut self.spectators);
+        for connection in connections {
+            if connection.send(&message).is_err() {
+                connection.close();
+            }
+        }
+        self.last = Some(frame);
+    }
+}
+
+// the inputs of a player's part among its messages, in the order they came
+// (anything else, e.g. inputs of another part, is not listened to)
+fn inputs(role: Role, messages: impl IntoIterator<Item = ClientMessage>) -> impl Iterator<Item = Event> {
+    messages.into_iter().filter_map(move |message| match message {
+        ClientMessage::Input { input } => input.parse::<Input>().ok().filter(|input| role.owns(*input)).map(Event::Input),
+        _ => None
+    })
+}
+
+// client design
+impl Client {
+    // join the match of a host to play a part of it
+    // -> the host's seed & settings, or why it did not let the player in
+    pub fn join<A: ToSocketAddrs>(address: A, role: Role) -> io::Result<(Client, u64, Settings)> {
+        Client::connect(address, ClientMessage::Join { role: role.to_string() }, Some(role))
+    }
+
+    // watch the match of a host, without playing any part of it
+    pub fn watch<A: ToSocketAddrs>(address: A) -> io::Result<(Client, u64, Settings)> {
+        Client::connect(address, ClientMessage::Watch, None)
+    }
+
+    fn connect<A: ToSocketAddrs>(address: A, hello: ClientMessage, role: Option<Role>) -> io::Result<(Client, u64, Settings)> {
+        let mut connection = Connection::new(TcpStream::connect(address)?)?;
+        connection.send(&hello)?;
+
+        let start = Instant::now();
+        while start.elapsed().as_secs_f64() < JOIN_TIMEOUT {
+            let messages = connection.receive::<HostMessage>()?;
+            if messages.is_empty() {
+                thread::sleep(Duration::from_millis(10));
+            }
+            // (the frame to catch up with may come along with the welcome)
+            let (mut welcome, mut frame) = (None, None);
+            for message in messages {
+                match message {
+                    HostMessage::Welcome { seed, settings } => welcome = Some((seed, settings)),
+                    HostMessage::Refused { reason } => {
+                        return Err(io::Error::new(io::ErrorKind::PermissionDenied, reason));
+                    }
+                    HostMessage::Frame(last) => frame = Some(last)
+                }
+            }
+            if let Some((seed, settings)) = welcome {
+                settings.validate().map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+                return Ok((Client { connection, role, frame }, seed, settings));
+            }
+        }
+        Err(io::Error::new(io::ErrorKind::TimedOut, "the host did not answer"))
+    }
+
+    // the part it plays, none for a spectator
+    pub fn role(&self) -> Option<Role> {
+        self.role
+    }
+
+    // send an input of its part to the host
+    pub fn send(&mut self, input: Input) -> io::Result<()> {
+        self.connection.send(&ClientMessage::Input { input: input.to_string() })
+    }
+
+    // the last frame the host sent since it was last asked, if any
+    // (an error once the host is gone)
+    pub fn poll(&mut self) -> io::Result<Option<Frame>> {
+        self.connection.flush()?;
+        let frame = self.connection.receive::<HostMessage>()?
+            .into_iter()
+            .filter_map(|message| match message {
+                HostMessage::Frame(frame) => Some(frame),
+                _ => None
+            })
+            .next_back();
+        let caught_up = self.frame.take();
+        Ok(frame.or(caught_up))
+    }
+}
+
+// connection design
+impl Connection {
+    fn new(stream: TcpStream) -> io::Result<Connection> {
+        stream.set_nonblocking(true)?;
+        // (inputs are tiny and should go out right away)
+        stream.set_nodelay(true)?;
+        Ok(Connection {
+            stream,
+            incoming: Vec::new(),
+            outgoing: Vec::new()
+        })
+    }
+
+    // queue a message as a line & send as much as the stream takes
+    fn send<T: Serialize>(&mut self, message: &T) -> io::Result<()> {
+        serde_json::to_writer(&mut self.outgoing, message)?;
+        self.outgoing.push(b'\n');
+        if self.outgoing.len() > MAX_BACKLOG {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "the other end does not keep up"));
+        }
+        self.flush()
+    }
+
+    // send what is queued, as far as the stream takes it
+    fn flush(&mut self) -> io::Result<()> {
+        while !self.outgoing.is_empty() {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(written) => {
+                    self.outgoing.drain(..written);
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err)
+            }
+        }
+        Ok(())
+    }
+
+    // every whole line received so far, as messages
+    // (an error once the other end is gone and nothing is left to read, or if it sends a line
+    // longer than MAX_LINE)
+    fn receive<T: DeserializeOwned>(&mut self) -> io::Result<Vec<T>> {
+        let mut closed = false;
+        let mut buffer = [0; 4096];
+        let mut received = 0;
+        // (whole lines never stay in incoming, so it starts with the line still coming)
+        let mut line = self.incoming.len();
+        while received < MAX_LINE {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => {
+                    closed = true;
+                    break;
+                }
+                Ok(read) => {
+                    self.incoming.extend_from_slice(&buffer[..read]);
+                    received += read;
+                    line = match buffer[..read].iter().rposition(|byte| *byte == b'\n') {
+                        Some(end) => read - end - 1,
+                        None => line + read
+                    };
+                    if line > MAX_LINE {
+                        return Err(io::Error::new(io::ErrorKind::InvalidData, "the other end sent too long a line"));
+                    }
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err)
+            }
+        }
+
+        let mut messages = Vec::new();
+        while let Some(end) = self.incoming.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.incoming.drain(..=end).collect();
+            let message = serde_json::from_slice(&line[..end])
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            messages.push(message);
+        }
+        if closed && messages.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the connection was closed"));
+        }
+        Ok(messages)
+    }
+
+    // stop talking to the other end
+    fn close(&mut self) {
+        let _ = self.stream.shutdown(std::net::Shutdown::Both);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::Direction;
+
+    // poll the host until it has seen as many events, or a second went by
+    fn events(host: &mut Host, game: &Game, count: usize) -> Vec<Event> {
+        let start = Instant::now();
+        let mut events = Vec::new();
+        while events.len() < count && start.elapsed() < Duration::from_secs(1) {
+            events.extend(host.poll(game, |_| false));
+            thread::sleep(Duration::from_millis(5));
+        }
+        events
+    }
+
+    // join or watch from another thread, the host letting it in meanwhile
+    fn connect<F>(host: &mut Host, game: &Game, connect: F) -> (io::Result<(Client, u64, Settings)>, Vec<Event>)
+    where
+        F: FnOnce(SocketAddr) -> io::Result<(Client, u64, Settings)> + Send + 'static
+    {
+        let address = host.local_addr().unwrap();
+        let client = thread::spawn(move || connect(address));
+        let mut events = Vec::new();
+        while !client.is_finished() {
+            events.extend(host.poll(game, |_| false));
+            thread::sleep(Duration::from_millis(5));
+        }
+        (client.join().unwrap(), events)
+    }
+
+    #[test]
+    fn players_join_watch_and_play_on_localhost() {
+        let game = Game::new(&Settings::default(), 42);
+        let mut host = Host::bind("127.0.0.1:0").unwrap();
+
+        // the welcome has the match's seed & settings
+        let (joined, joins) = connect(&mut host, &game, |address| Client::join(address, Role::Snake));
+        let (mut player, seed, settings) = joined.unwrap();
+        assert_eq!((seed, &settings), (42, game.settings()));
+        assert_eq!(player.role(), Some(Role::Snake));
+        assert_eq!(joins, vec![Event::Joined(Role::Snake)]);
+
+        // a part already played is refused, one not on the board too
+        let (taken, _) = connect(&mut host, &game, |address| Client::join(address, Role::Snake));
+        let err = taken.err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert!(err.to_string().contains("snake is taken"));
+        let (missing, _) = connect(&mut host, &game, |address| Client::join(address, Role::Paddle(Side::Top)));
+        assert!(missing.err().unwrap().to_string().contains("no top-paddle"));
+
+        // inputs of the player's part reach the host, others do not
+        player.send(Input::PaddleUp(Side::Left)).unwrap();
+        player.send(Input::Snake(Direction::Up)).unwrap();
+        assert_eq!(events(&mut host, &game, 1), vec![Event::Input(Input::Snake(Direction::Up))]);
+
+        // spectators see the frames, and catch up with the last one when coming in late
+        let frame = Frame::new(&game);
+        host.send(&frame);
+        let (watched, watching) = connect(&mut host, &game, Client::watch);
+        let (mut spectator, _, _) = watched.unwrap();
+        assert_eq!(spectator.role(), None);
+        assert_eq!(watching, vec![Event::Watching]);
+        assert_eq!(spectator.poll().unwrap(), Some(frame));
+
+        drop(player);
+        drop(spectator);
+        let mut gone = events(&mut host, &game, 2);
+        gone.sort_by_key(|event| format!("{:?}", event));
+        assert_eq!(gone, vec![Event::Left(Role::Snake), Event::StoppedWatching]);
+    }
+
+    #[test]
+    fn silent_connections_are_not_kept_waiting() {
+        let game = Game::new(&Settings::default(), 1);
+        let mut host = Host::bind("127.0.0.1:0").unwrap();
+        let address = host.local_addr().unwrap();
+        let streams: Vec<TcpStream> = (0..MAX_PENDING + 4).map(|_| TcpStream::connect(address).unwrap()).collect();
+        events(&mut host, &game, 1);
+        assert_eq!(host.pending.len(), MAX_PENDING);
+
+        // (as if they had come in long ago)
+        for (since, _) in &mut host.pending {
+            *since -= HELLO_TIMEOUT;
+        }
+        host.poll(&game, |_| false);
+        assert!(host.pending.is_empty());
+        drop(streams);
+    }
+
+    // the next frame the client gets, waiting a little for it
+    fn next_frame(client: &mut Client) -> Option<Frame> {
+        for _ in 0..200 {
+            if let Some(frame) = client.poll().unwrap() {
+                return Some(frame);
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        None
+    }
+
+    #[test]
+    fn spectators_catch_up_with_the_last_frame_and_see_the_next_ones() {
+        let mut game = Game::new(&Settings::default(), 7);
+        let mut host = Host::bind("127.0.0.1:0").unwrap();
+        let first = Frame::new(&game);
+        host.send(&first);
+
+        let (watched, events) = connect(&mut host, &game, Client::watch);
+        let (mut spectator, _, _) = watched.unwrap();
+        assert_eq!(events, vec![Event::Watching]);
+        assert_eq!(host.spectators(), 1);
+        assert_eq!(spectator.role(), None);
+        assert_eq!(next_frame(&mut spectator), Some(first));
+
+        game.step();
+        let second = Frame::new(&game);
+        host.send(&second);
+        assert_eq!(next_frame(&mut spectator), Some(second));
+
+        // (a spectator's inputs are not listened to, whatever part they are for)
+        spectator.send(Input::Snake(Direction::Down)).unwrap();
+        spectator.poll().unwrap();
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(host.poll(&game, |_| false), Vec::new());
+
+        drop(spectator);
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(host.poll(&game, |_| false), vec![Event::StoppedWatching]);
+    }
+
+    #[test]
+    fn inputs_sent_along_with_the_hello_are_not_lost() {
+        let game = Game::new(&Settings::default(), 1);
+        let mut host = Host::bind("127.0.0.1:0").unwrap();
+        let mut stream = TcpStream::connect(host.local_addr().unwrap()).unwrap();
+        stream.write_all(b"{\"Join\":{\"role\":\"snake\"}}\n{\"Input\":{\"input\":\"snake-down\"}}\n").unwrap();
+        assert_eq!(
+            events(&mut host, &game, 2),
+            vec![Event::Joined(Role::Snake), Event::Input(Input::Snake(Direction::Down))]
+        );
+    }
+
+    #[test]
+    fn frames_go_out_on_ticks_and_not_on_the_clock_alone() {
+        let mut game = Game::new(&Settings::default(), 7);
+        let mut host = Host::bind("127.0.0.1:0").unwrap();
+        let (watched, _) = connect(&mut host, &game, Client::watch);
+        let (mut spectator, _, _) = watched.unwrap();
+
+        game.update(0.1);
+        host.send(&Frame::new(&game));
+        let first = next_frame(&mut spectator).unwrap();
+        assert_eq!(first.motion.progress, 0.0);
+        game.update(0.1);
+        host.send(&Frame::new(&game));
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(spectator.poll().unwrap(), None);
+
+        game.step();
+        host.send(&Frame::new(&game));
+        assert_ne!(next_frame(&mut spectator).unwrap().view, first.view);
+    }
+}
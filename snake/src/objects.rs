@@ -1,16 +1,11 @@
-// LinkedList => snake & pedals
+// LinkedList => snake & paddles
 // Vector => enemy
 use std::collections::LinkedList; 
-use piston_window::{Context, G2d};
-use piston_window::types::Color;
+use std::str::FromStr;
 
-use crate::draw::draw_block;
+use serde::{Deserialize, Serialize};
 
-const SNAKE_COLOR: Color = [0.00, 0.80, 0.00, 1.0]; //RGB, opacity
-const PEDAL_COLOR: Color = [0.00, 0.00, 0.00, 1.0]; 
-const ENEMY_COLOR: Color = [0.80, 0.00, 0.00, 1.0];
-
-#[derive(Copy, Clone, Eq, PartialEq, PartialOrd)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Serialize, Deserialize)]
 // direction type
 pub enum Direction {
     Up,
@@ -32,58 +27,87 @@ impl Direction {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
-struct Block {
-    x: i32,
-    y: i32
+// one cell of the gameboard
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Block {
+    pub x: i32,
+    pub y: i32
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Snake {
     direction: Direction,
     body: LinkedList<Block>,
     tail: Option<Block>
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Enemy {
-    gang: Vec<Block>
+    gang: Vec<Block>,
+    // size of the gameboard the enemies are laid out on
+    width: i32,
+    height: i32
 }
 
-pub struct LeftPedal {
-    body: LinkedList<Block>
+// side of the board a paddle guards
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Left,
+    Right,
+    Top,
+    Bottom
 }
 
-pub struct RightPedal {
-    body: LinkedList<Block>    
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Paddle {
+    side: Side,
+    // blocks moved per input
+    speed: i32,
+    // first block first (top on the left & right, left on the top & bottom)
+    body: LinkedList<Block>,
+    // first & last block the paddle can reach along its side (just inside the corners)
+    first: i32,
+    last: i32
 }
 
 // snake design
 impl Snake {
     // create a new snake
     pub fn new(x: i32, y: i32) -> Snake {
+        Snake::facing(x, y, Direction::Right)
+    }
+
+    // create a new snake with its tail at (x, y), facing a direction
+    pub fn facing(x: i32, y: i32, direction: Direction) -> Snake {
+        let (head_x, head_y) = match direction {
+            Direction::Up => (x, y - 1),
+            Direction::Down => (x, y + 1),
+            Direction::Left => (x - 1, y),
+            Direction::Right => (x + 1, y)
+        };
+
         let mut body: LinkedList<Block> = LinkedList::new();
         // start with a length of 2
         body.push_back(Block {
-            x : x+1,
-            y
+            x: head_x,
+            y: head_y
         });
         body.push_back(Block {
-            x : x,
+            x,
             y
         });
 
         Snake {
-            direction: Direction::Right,
+            direction,
             body,
             tail: None
         }
-
     }
 
-    // draw snake
-    pub fn draw(&self, con: &Context, g: &mut G2d) {
-        for block in &self.body {
-            draw_block(SNAKE_COLOR, block.x, block.y, con, g);
-        }
+    // blocks of the snake, head first
+    pub fn body(&self) -> impl Iterator<Item = &Block> {
+        self.body.iter()
     }
 
     // return head's position
@@ -96,9 +120,8 @@ impl Snake {
 
     // moving directions
     pub fn move_forward(&mut self, dir:Option<Direction>) {
-        match dir {
-            Some(d) => self.direction = d,
-            None => ()
+        if let Some(d) = dir {
+            self.direction = d;
         }
 
         // get the head position
@@ -139,11 +162,7 @@ impl Snake {
     pub fn next_head(&self, dir: Option<Direction>) -> (i32, i32) {
         let (head_x, head_y): (i32, i32) = self.head_position();
 
-        let mut moving_dir = self.direction;
-        match dir {
-            Some(d) => moving_dir = d,
-            None => {}
-        }
+        let moving_dir = dir.unwrap_or(self.direction);
         match moving_dir {
             Direction::Up => (head_x, head_y - 1),
             Direction::Down => (head_x, head_y + 1),
@@ -154,12 +173,12 @@ impl Snake {
 
     // adding a new block to snake's tail
     pub fn restore_tail(&mut self) {
-        let block = self.tail.clone().unwrap();
+        let block = self.tail.unwrap();
         self.body.push_back(block);        
     }
 
     // check if the next block position of the head overlaps with the current tail block position
-    pub fn overlap_tail(&mut self, x: i32, y: i32) -> bool {
+    pub fn overlap_tail(&self, x: i32, y: i32) -> bool {
         let mut ch = 0;
 
         for block in &self.body {
@@ -174,7 +193,7 @@ impl Snake {
                 break;
             }
         }
-        return false;
+        false
     }  
 }
 
@@ -182,313 +201,231 @@ impl Snake {
 impl Enemy {
 
     // create a enemy vector
-    pub fn new() -> Enemy {
-        let mut gang : Vec<Block> = Vec::new();
-
+    pub fn new(width: i32, height: i32) -> Enemy {
+        let mut enemy = Enemy {
+            gang: Vec::new(),
+            width,
+            height
+        };
         // initial enemy position
-        gang.push(Block {
-            x: 10,
-            y: 6
-        });
+        enemy.add_enemy();
+        enemy
+    }
 
-        Enemy {
-            gang
-        }
+    // blocks of every enemy on the gameboard
+    pub fn blocks(&self) -> impl Iterator<Item = &Block> {
+        self.gang.iter()
     }
-    
-    // draw enemies
-    pub fn draw(&self, con: &Context, g: &mut G2d) {
-        for e in &self.gang {
-            draw_block(ENEMY_COLOR, e.x, e.y, con, g);
+
+    // designated locations of enemies, in the order they show up
+    // -> 4 rows at every fifth of the height,
+    //    2 enemies (thirds of the width) on the outer rows and 3 (quarters) on the inner ones
+    // e.g. (10, 6), (20, 6), (7, 12), (15, 12), (22, 12) ... on a 30x30 board
+    fn layout(&self) -> Vec<Block> {
+        let (w, h) = (self.width, self.height);
+        let mut layout = Vec::new();
+        for row in 1..=4 {
+            let y = h * row / 5;
+            let columns = if row == 1 || row == 4 { 3 } else { 4 };
+            for column in 1..columns {
+                layout.push(Block {
+                    x: w * column / columns,
+                    y
+                });
+            }
         }
+        layout
     }
 
     // adding a new enemy into the gameboard
+    // (nothing happens once every designated location is taken)
     pub fn add_enemy(&mut self) {
-        let last_index: usize = self.gang.len()-1;        
-        let mut new_point_x: i32 = 0;
-        let mut new_point_y: i32 = 0;
-
-        // designated locations of enemies
-        if last_index == 0 {
-            new_point_x = 20;
-            new_point_y = 6;            
-        }
-
-        if last_index == 1 {
-            new_point_x = 7;
-            new_point_y = 12;         
-        }
-
-        if last_index == 2 {
-            new_point_x = 15;
-            new_point_y = 12;
-        }
-
-        if last_index == 3 {
-            new_point_x = 22;
-            new_point_y = 12;
-        }
-
-        if last_index == 4 {
-            new_point_x = 7;
-            new_point_y = 18;
-        }
-
-        if last_index == 5 {
-            new_point_x = 15;
-            new_point_y = 18;            
-        }
-
-        if last_index == 6 {
-            new_point_x = 22;
-            new_point_y = 18;            
+        if let Some(new_block) = self.layout().get(self.gang.len()) {
+            // add a new enemy
+            self.gang.push(*new_block);
         }
-
-        if last_index == 7 {
-            new_point_x = 10;
-            new_point_y = 24;            
-        }
-
-        if last_index == 8 {
-            new_point_x = 20;
-            new_point_y = 24;            
-        }                        
-
-        let new_block: Block = Block {
-            x : new_point_x, 
-            y : new_point_y
-        };
-        // add a new enemy 
-        self.gang.push(new_block);
-        return;
-        
     }
-
+    
     // check if the snake contacts with any of the enemy
-    pub fn contact(&mut self, x: i32, y: i32) -> bool {
+    pub fn contact(&self, x: i32, y: i32) -> bool {
         let block = Block {
             x, y
         };
 
         // check if any coordinates of enemies match with snake's head position
-        if self.gang.contains(&block) {
-            return true;
-        }
-
-        return false;
+        self.gang.contains(&block)
     }
 }
 
-// left pedal design
-impl LeftPedal {
-    // create a new left pedal
-    pub fn new() -> LeftPedal {
-        let mut body: LinkedList<Block> = LinkedList::new();
-        // pedal - length of 5
-        body.push_back(Block {
-            x : 0,
-            y : 1
-        });
-        body.push_back(Block {
-            x : 0,
-            y : 2
-        });
-        body.push_back(Block {
-            x : 0,
-            y : 3
-        });
-        body.push_back(Block {
-            x : 0,
-            y : 4
-        });
-        body.push_back(Block {
-            x : 0,
-            y : 5
-        });
-
-        LeftPedal {
-            body            
+// side design
+impl Side {
+    // true for the paddles moving up & down
+    pub fn is_vertical(&self) -> bool {
+        match *self {
+            Side::Left | Side::Right => true,
+            Side::Top | Side::Bottom => false
         }
     }
+}
 
-    // draw left pedal
-    pub fn draw(&self, con: &Context, g: &mut G2d) {
-        for block in &self.body {
-            draw_block(PEDAL_COLOR, block.x, block.y, con, g);
+impl FromStr for Side {
+    type Err = String;
+
+    // read a side from its name, as in the settings file
+    fn from_str(s: &str) -> Result<Side, String> {
+        match s {
+            "left" => Ok(Side::Left),
+            "right" => Ok(Side::Right),
+            "top" => Ok(Side::Top),
+            "bottom" => Ok(Side::Bottom),
+            _ => Err(format!("unknown side '{}' (left, right, top or bottom)", s))
         }
     }
+}
 
-    // return head's position
-    pub fn head_position(&self) -> (i32, i32) {        
-        let head_block = self.body.front().unwrap();            
-        (head_block.x, head_block.y)
-    }
-
-    // return tail's position
-    pub fn tail_position(&self) -> (i32, i32) {
-        let tail_block = self.body.back().unwrap();
-        (tail_block.x, tail_block.y)
-    }
+// paddle design
+impl Paddle {
+    // create a new paddle on the side of a width x height board
+    // initially located at the start of the side (top or left)
+    pub fn new(side: Side, length: i32, speed: i32, width: i32, height: i32) -> Paddle {
+        // the line the paddle lives on & how far it reaches along it
+        let (line, last) = match side {
+            Side::Left => (0, height - 2),
+            Side::Right => (width - 1, height - 2),
+            Side::Top => (0, width - 2),
+            Side::Bottom => (height - 1, width - 2)
+        };
 
-    // moving up
-    pub fn move_up(&mut self) {        
-        // get the head position
-        let (last_x, last_y): (i32, i32) = self.head_position();
-        // stop if it reaches the top
-        if last_y == 1 {
-            return;
+        let mut body: LinkedList<Block> = LinkedList::new();
+        for along in 1..=length {
+            body.push_back(Paddle::block(side, line, along));
         }
 
-        // add another block to one pixel above the original pedal
-        let new_block = Block {
-                x: last_x,
-                y: last_y - 1
-        };
-        // add to the linkedlist
-        self.body.push_front(new_block);
-        // remove the very bottom block, which is the tail node
-        self.body.pop_back().unwrap();        
+        Paddle {
+            side,
+            speed,
+            body,
+            first: 1,
+            last
+        }
     }
 
-    // moving down
-    pub fn move_down(&mut self) {        
-        // get the tail position
-        let (last_x, last_y): (i32, i32) = self.tail_position();
-
-        // stop if it reaches the bottom 
-        if last_y == 28 {
-            return;
+    // block on the line of the side at a position along it
+    fn block(side: Side, line: i32, along: i32) -> Block {
+        if side.is_vertical() {
+            Block {
+                x: line,
+                y: along
+            }
+        } else {
+            Block {
+                x: along,
+                y: line
+            }
         }
-
-        // add another block to one pixel below the original pedal
-        let new_block = Block {
-                x: last_x,
-                y: last_y + 1
-        };
-        // add to the linkedlist
-        self.body.push_back(new_block);
-        // remove the very top block, which is the head node
-        self.body.pop_front().unwrap();
     }
 
-    // check if the pedal blocks the food
-    pub fn block_food(&mut self, y: i32) -> bool {
-        // comparing the positions of the food and of the pedal's body
-        for block in &self.body {
-            if y == block.y {
-                return true;
-            }            
+    // position of a block along the side
+    fn along(&self, block: &Block) -> i32 {
+        if self.side.is_vertical() {
+            block.y
+        } else {
+            block.x
         }
-        return false;
     }
 
-}
-
-// right pedal design
-impl RightPedal {
-    // create a new right pedal
-    pub fn new() -> RightPedal {
-        let mut body: LinkedList<Block> = LinkedList::new();
-        // pedal - length of 5
-        body.push_back(Block {
-            x : 29,
-            y : 1
-        });
-        body.push_back(Block {
-            x : 29,
-            y : 2
-        });
-        body.push_back(Block {
-            x : 29,
-            y : 3
-        });
-        body.push_back(Block {
-            x : 29,
-            y : 4
-        });
-        body.push_back(Block {
-            x : 29,
-            y : 5
-        });
+    // return the side it guards
+    pub fn side(&self) -> Side {
+        self.side
+    }
 
-        RightPedal {
-            body
-        }
+    // return its length in blocks
+    pub fn length(&self) -> i32 {
+        self.body.len() as i32
     }
 
-    // draw right pedal
-    pub fn draw(&self, con: &Context, g: &mut G2d) {
-        for block in &self.body {
-            draw_block(PEDAL_COLOR, block.x, block.y, con, g);
-        }
+    // return the blocks it moves per input
+    pub fn speed(&self) -> i32 {
+        self.speed
     }
 
-    // return head's position
-    pub fn head_position(&self) -> (i32, i32) {        
-        let head_block = self.body.front().unwrap();            
-        (head_block.x, head_block.y)
+    // blocks of the paddle, first block first
+    pub fn body(&self) -> impl Iterator<Item = &Block> {
+        self.body.iter()
     }
 
-    // return tail's position
-    pub fn tail_position(&self) -> (i32, i32) {
+    // return the range it covers along its side
+    pub fn span(&self) -> (i32, i32) {
+        let head_block = self.body.front().unwrap();
         let tail_block = self.body.back().unwrap();
-        (tail_block.x, tail_block.y)
+        (self.along(head_block), self.along(tail_block))
     }
 
-    // moving up
-    pub fn move_up(&mut self) {
-        
-        // get the head position
-        let (last_x, last_y): (i32, i32) = self.head_position();
-
-        // stop if it reaches the top
-        if last_y == 1 {
-            return;
-        }
-
-        // add another block to one pixel above the original pedal
-        let new_block = Block {
-                x: last_x,
-                y: last_y - 1
-        };
-        // add it to the linkedlist
-        self.body.push_front(new_block);
-        // remove the very bottom block, which is the tail node
-        self.body.pop_back().unwrap();
+    // moving up (left on the top & bottom), speed blocks at a time
+    pub fn move_up(&mut self) {        
+        self.slide_up(self.speed);
     }
 
-    // moving down
+    // moving down (right on the top & bottom), speed blocks at a time
     pub fn move_down(&mut self) {
-        // get the tail position
-        let (last_x, last_y): (i32, i32) = self.tail_position();
+        self.slide_down(self.speed);
+    }
 
-        // stop if it reaches the bottom
-        if last_y == 28 {
-            return;
-        }
+    // moving up (left on the top & bottom) by some blocks
+    pub fn slide_up(&mut self, blocks: i32) {
+        for _ in 0..blocks {
+            let head_block = *self.body.front().unwrap();
+            // stop if it reaches the start of the side
+            if self.along(&head_block) == self.first {
+                return;
+            }
 
-        // add another block to one pixel below the original pedal
-        let new_block = Block {
-                x: last_x,
-                y: last_y + 1
-        };
-        // add it to the linkedlist
-        self.body.push_back(new_block);
-        // remove the very top block, which is the head node
-        self.body.pop_front().unwrap();                
+            // add another block in front of the paddle
+            let new_block = match self.side {
+                Side::Left | Side::Right => Block {
+                    x: head_block.x,
+                    y: head_block.y - 1
+                },
+                Side::Top | Side::Bottom => Block {
+                    x: head_block.x - 1,
+                    y: head_block.y
+                }
+            };
+            self.body.push_front(new_block);
+            // remove the block at the other end, which is the tail node
+            self.body.pop_back().unwrap();
+        }
     }
 
-    // check if the pedal blocks the food
-    pub fn block_food(&mut self, y: i32) -> bool {
-        
-        // comparing the positions of the food and of the pedal's body
-        for block in &self.body {
-            if y == block.y {
-                return true;
-            }            
+    // moving down (right on the top & bottom) by some blocks
+    pub fn slide_down(&mut self, blocks: i32) {
+        for _ in 0..blocks {
+            let tail_block = *self.body.back().unwrap();
+            // stop if it reaches the end of the side
+            if self.along(&tail_block) == self.last {
+                return;
+            }
+
+            // add another block behind the paddle
+            let new_block = match self.side {
+                Side::Left | Side::Right => Block {
+                    x: tail_block.x,
+                    y: tail_block.y + 1
+                },
+                Side::Top | Side::Bottom => Block {
+                    x: tail_block.x + 1,
+                    y: tail_block.y
+                }
+            };
+            self.body.push_back(new_block);
+            // remove the block at the other end, which is the head node
+            self.body.pop_front().unwrap();
         }
-        return false;
     }
 
-}
\ No newline at end of file
+    // check if the paddle blocks the food at a position along its side
+    pub fn block_food(&self, along: i32) -> bool {
+        // comparing the positions of the food and of the paddle's body
+        self.body.iter().any(|block| along == self.along(block))
+    }
+}
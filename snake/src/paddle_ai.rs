@@ -0,0 +1,225 @@
+// computer-controlled paddle
+// predicts where the food meets its side and moves there,
+// with a reaction delay, a chance to misjudge the aim & a speed limit set by the difficulty
+use std::fmt;
+use std::str::FromStr;
+
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+
+use crate::controller::Controller;
+use crate::game::Game;
+use crate::input::Input;
+use crate::objects::{Block, Side};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard
+}
+
+pub struct PaddleAi {
+    side: Side,
+
+    // ticks to wait after the food changes course before moving
+    reaction: u32,
+    // chance to aim just past the paddle's reach (and miss)
+    miss_chance: f64,
+    // paddle moves per tick
+    max_moves: usize,
+
+    // predicted meeting point the current aim was taken for, and the aiming error
+    course: Option<i32>,
+    offset: i32,
+    // where the food was on the last tick
+    last_food: Option<Block>,
+    // ticks left before reacting to the current course
+    waiting: u32,
+
+    rng: Pcg32
+}
+
+// difficulty design
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    // reaction delay (ticks), chance to misjudge the aim, moves per tick
+    fn profile(&self) -> (u32, f64, usize) {
+        match *self {
+            Difficulty::Easy => (3, 0.2, 2),
+            Difficulty::Normal => (2, 0.07, 2),
+            Difficulty::Hard => (0, 0.0, 3)
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match *self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard"
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Difficulty, String> {
+        Difficulty::ALL.iter()
+            .find(|difficulty| difficulty.name() == s)
+            .copied()
+            .ok_or_else(|| format!("unknown difficulty '{}' (easy, normal or hard)", s))
+    }
+}
+
+// paddle AI design
+impl PaddleAi {
+    // computer player for the paddle on a side
+    // the seed drives its aiming errors
+    pub fn new(side: Side, difficulty: Difficulty, seed: u64) -> PaddleAi {
+        let (reaction, miss_chance, max_moves) = difficulty.profile();
+        PaddleAi {
+            side,
+            reaction,
+            miss_chance,
+            max_moves,
+            course: None,
+            offset: 0,
+            last_food: None,
+            waiting: 0,
+            rng: Pcg32::seed_from_u64(seed)
+        }
+    }
+
+    // return the side it controls
+    pub fn side(&self) -> Side {
+        self.side
+    }
+
+    // how far from the predicted point the middle of the paddle aims
+    // -> anywhere the paddle still covers the point, or just past its reach when misjudging
+    fn aim_offset(&mut self, length: i32) -> i32 {
+        if self.rng.gen_bool(self.miss_chance) {
+            let past_reach = length / 2 + 2;
+            if self.rng.gen() { past_reach } else { -past_reach }
+        } else {
+            let reach = (length - 1) / 2;
+            self.rng.gen_range(-reach, reach + 1)
+        }
+    }
+
+    // where along the side the food will next be checked against the paddle
+    fn predict(&self, game: &Game) -> Option<i32> {
+        let food = game.food()?;
+        let (width, height) = (game.width(), game.height());
+
+        // long enough to cross the board twice
+        let ticks = 2 * (width + height) as usize;
+        game.food_path(food, game.food_speed(), ticks)
+            .into_iter()
+            .find_map(|(block, _)| match self.side {
+                Side::Left if block.x == 1 => Some(block.y),
+                Side::Right if block.x == width - 2 => Some(block.y),
+                Side::Top if block.y <= 1 => Some(block.x),
+                Side::Bottom if block.y >= height - 2 => Some(block.x),
+                _ => None
+            })
+    }
+}
+
+impl Controller for PaddleAi {
+    fn control(&mut self, game: &Game) -> Vec<Input> {
+        let paddle = match game.paddle(self.side) {
+            Some(paddle) => paddle,
+            None => return Vec::new()
+        };
+
+        // a new course (the food bounced off the other side, got eaten or the match restarted):
+        // pick a new aiming error & wait to react
+        let arrival = self.predict(game);
+        let food = game.food();
+        let jumped = match (self.last_food, food) {
+            (Some(last), Some(food)) => (last.x - food.x).abs() > 1 || (last.y - food.y).abs() > 1,
+            _ => true
+        };
+        self.last_food = food;
+        if arrival != self.course || jumped {
+            self.course = arrival;
+            self.offset = self.aim_offset(paddle.length());
+            self.waiting = self.reaction;
+        }
+        if self.waiting > 0 {
+            self.waiting -= 1;
+            return Vec::new();
+        }
+
+        let target = match arrival {
+            Some(along) => along + self.offset,
+            None => return Vec::new()
+        };
+
+        // move the middle of the paddle onto the target,
+        // without overshooting it by more than half a move
+        let (first, last) = paddle.span();
+        let speed = paddle.speed();
+        let mut distance = target - (first + last) / 2;
+        let mut inputs = Vec::new();
+        while inputs.len() < self.max_moves && distance.abs() * 2 > speed {
+            if distance < 0 {
+                inputs.push(Input::PaddleUp(self.side));
+                distance += speed;
+            } else {
+                inputs.push(Input::PaddleDown(self.side));
+                distance -= speed;
+            }
+        }
+        inputs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::Direction;
+    use crate::settings::Settings;
+
+    // whether the right paddle keeps the food in the first time it comes to its side
+    // (the snake turns down on the way, out of the food's path)
+    fn first_block(difficulty: Difficulty, seed: u64) -> bool {
+        let mut game = Game::new(&Settings::default(), seed);
+        let mut ai = PaddleAi::new(Side::Right, difficulty, seed);
+        let period = game.settings().speed.moving_period;
+        for tick in 0..100 {
+            if tick == 10 {
+                game.key_pressed(Input::Snake(Direction::Down));
+            }
+            for input in ai.control(&game) {
+                game.key_pressed(input);
+            }
+            game.update(period + 0.01);
+            match game.food() {
+                Some(food) if food.x == game.width() - 2 => return !game.game_over(),
+                _ => assert!(!game.game_over(), "the round ended before the food came")
+            }
+        }
+        panic!("the food never came");
+    }
+
+    #[test]
+    fn hard_paddles_block_every_time() {
+        assert!((0..20).all(|seed| first_block(Difficulty::Hard, seed)));
+    }
+
+    #[test]
+    fn easy_paddles_miss_sometimes() {
+        let misses = (0..50).filter(|seed| !first_block(Difficulty::Easy, *seed)).count();
+        assert!(misses > 0 && misses < 50, "{} misses out of 50", misses);
+    }
+}
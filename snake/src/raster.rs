@@ -0,0 +1,324 @@
+// software rasterizer: draws a view into an RGBA image in memory, without any GPU
+// -> screenshots (PNG) & animations (GIF) from headless machines
+// the board looks as in the window, with a score bar under it written in a tiny block font
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::settings::{Color, Colors};
+use crate::view::View;
+
+// RGBA pixels, row by row
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>
+}
+
+// animated GIF written frame by frame, looping forever
+pub struct Animation<W: Write> {
+    encoder: gif::Encoder<W>,
+    width: u16,
+    height: u16
+}
+
+// 3x5 glyphs of the block font, one row per byte (bit 2 = left column)
+const FONT: [(char, [u8; 5]); 29] = [
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', [0b111, 0b001, 0b111, 0b001, 0b111]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('6', [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b001, 0b001, 0b001]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b111]),
+    ('A', [0b010, 0b101, 0b111, 0b101, 0b101]),
+    ('C', [0b111, 0b100, 0b100, 0b100, 0b111]),
+    ('D', [0b110, 0b101, 0b101, 0b101, 0b110]),
+    ('E', [0b111, 0b100, 0b110, 0b100, 0b111]),
+    ('G', [0b111, 0b100, 0b101, 0b101, 0b111]),
+    ('I', [0b111, 0b010, 0b010, 0b010, 0b111]),
+    ('L', [0b100, 0b100, 0b100, 0b100, 0b111]),
+    ('M', [0b101, 0b111, 0b111, 0b101, 0b101]),
+    ('N', [0b110, 0b101, 0b101, 0b101, 0b101]),
+    ('O', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('P', [0b110, 0b101, 0b110, 0b100, 0b100]),
+    ('R', [0b110, 0b101, 0b110, 0b101, 0b101]),
+    ('S', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('V', [0b101, 0b101, 0b101, 0b101, 0b010]),
+    ('W', [0b101, 0b101, 0b111, 0b111, 0b101]),
+    ('Y', [0b101, 0b101, 0b010, 0b010, 0b010]),
+    ('!', [0b010, 0b010, 0b010, 0b000, 0b010]),
+    ('-', [0b000, 0b000, 0b111, 0b000, 0b000]),
+    (' ', [0b000, 0b000, 0b000, 0b000, 0b000])
+];
+
+// image design
+impl Image {
+    // an image filled with one color
+    pub fn new(width: u32, height: u32, color: Color) -> Image {
+        let pixel = to_rgba(color);
+        Image {
+            width,
+            height,
+            pixels: pixel.iter().copied().cycle().take((width * height * 4) as usize).collect()
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    // RGBA bytes, row by row
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    // draw a rectangle over the image, blended by its opacity
+    // (parts outside the image are left out)
+    pub fn fill(&mut self, x: i64, y: i64, width: i64, height: i64, color: Color) {
+        let (left, top) = (x.max(0), y.max(0));
+        let right = (x + width).min(self.width as i64);
+        let bottom = (y + height).min(self.height as i64);
+        let alpha = color[3].clamp(0.0, 1.0);
+        let over = to_rgba(color);
+
+        for row in top..bottom {
+            for column in left..right {
+                let index = ((row * self.width as i64 + column) * 4) as usize;
+                let pixel = &mut self.pixels[index..index + 4];
+                for channel in 0..3 {
+                    let under = pixel[channel] as f32;
+                    pixel[channel] = (over[channel] as f32 * alpha + under * (1.0 - alpha)).round() as u8;
+                }
+                pixel[3] = 255;
+            }
+        }
+    }
+
+    // write text in the block font, each font pixel `size` pixels wide
+    // (lowercase is drawn as uppercase, unknown characters as spaces)
+    pub fn text(&mut self, text: &str, x: i64, y: i64, size: i64, color: Color) {
+        for (index, c) in text.chars().enumerate() {
+            let rows = FONT.iter()
+                .find(|(glyph, _)| *glyph == c.to_ascii_uppercase())
+                .map(|(_, rows)| *rows)
+                .unwrap_or([0; 5]);
+            let left = x + index as i64 * 4 * size;
+            for (row, bits) in rows.iter().enumerate() {
+                for column in 0..3 {
+                    if bits & (0b100 >> column) != 0 {
+                        self.fill(left + column * size, y + row as i64 * size, size, size, color);
+                    }
+                }
+            }
+        }
+    }
+
+    // save as a PNG file
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.width, self.height);
+        encoder.set_color(png::ColorType::RGBA);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(to_io_error)?;
+        writer.write_image_data(&self.pixels).map_err(to_io_error)
+    }
+}
+
+// width of a text in the block font, in pixels
+fn text_width(text: &str, size: i64) -> i64 {
+    (text.chars().count() as i64 * 4 - 1).max(0) * size
+}
+
+// draw every game component, block_size pixels per block
+// -> same layers as the window, plus the score bar
+pub fn render(view: &View, colors: &Colors, block_size: u32) -> Image {
+    let block = block_size.max(1) as i64;
+    let (width, height) = (view.width as i64 * block, view.height as i64 * block);
+    // font pixels of the score bar
+    let size = (block / 4).max(1);
+    let bar = 7 * size;
+
+    let mut image = Image::new(width as u32, (height + bar) as u32, colors.background);
+    let draw_block = |image: &mut Image, x: i32, y: i32, color: Color| {
+        image.fill(x as i64 * block, y as i64 * block, block, block, color);
+    };
+
+    // snakes
+    for (index, body) in view.snakes.iter().enumerate() {
+        for b in body {
+            draw_block(&mut image, b.x, b.y, colors.snake_color(index));
+        }
+    }
+
+    // paddles
+    for (_, body) in &view.paddles {
+        for b in body {
+            draw_block(&mut image, b.x, b.y, colors.paddle);
+        }
+    }
+
+    // enemy
+    for b in &view.enemies {
+        draw_block(&mut image, b.x, b.y, colors.enemy);
+    }
+
+    // food
+    if let Some(food) = view.food {
+        draw_block(&mut image, food.x, food.y, colors.food);
+    }
+
+    // boundaries
+    image.fill(0, 0, width, block, colors.border);
+    image.fill(0, height - block, width, block, colors.border);
+    image.fill(0, 0, block, height, colors.side);
+    image.fill(width - block, 0, block, height, colors.side);
+
+    // game over & game win screens, with their statements
+    if view.game_over {
+        image.fill(0, 0, width, height, colors.game_over);
+    } else if view.game_win {
+        image.fill(0, 0, width, height, colors.game_win);
+    }
+    if let Some(statement) = view.statement() {
+        let statement_size = (width / (text_width(&statement, 1) + 2)).clamp(1, block);
+        let x = (width - text_width(&statement, statement_size)) / 2;
+        let y = (height - 5 * statement_size) / 2;
+        image.text(&statement, x, y, statement_size, colors.text);
+    }
+
+    // score bar
+    image.text(&format!("SCORE {}", view.score_text()), size, height + size, size, colors.text);
+
+    image
+}
+
+// animation design
+impl Animation<BufWriter<File>> {
+    // start a GIF file of frames width x height
+    pub fn create<P: AsRef<Path>>(path: P, width: u32, height: u32) -> io::Result<Animation<BufWriter<File>>> {
+        Animation::new(BufWriter::new(File::create(path)?), width, height)
+    }
+}
+
+impl<W: Write> Animation<W> {
+    pub fn new(out: W, width: u32, height: u32) -> io::Result<Animation<W>> {
+        let (width, height) = (to_u16(width)?, to_u16(height)?);
+        let mut encoder = gif::Encoder::new(out, width, height, &[]).map_err(to_io_error)?;
+        encoder.set_repeat(gif::Repeat::Infinite).map_err(to_io_error)?;
+        Ok(Animation {
+            encoder,
+            width,
+            height
+        })
+    }
+
+    // add a frame shown for some seconds (GIFs count in hundredths of a second)
+    pub fn push(&mut self, image: &Image, seconds: f64) -> io::Result<()> {
+        if image.width != self.width as u32 || image.height != self.height as u32 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "frame size changed"));
+        }
+
+        // the board only has a handful of colors: keep them exact if they fit in a palette
+        let mut frame = match palette(&image.pixels) {
+            Some((palette, indices)) => gif::Frame::from_palette_pixels(self.width, self.height, &indices, &palette, None),
+            None => {
+                let mut pixels = image.pixels.clone();
+                gif::Frame::from_rgba_speed(self.width, self.height, &mut pixels, 10)
+            }
+        };
+        frame.delay = (seconds * 100.0).round().clamp(1.0, u16::MAX as f64) as u16;
+        self.encoder.write_frame(&frame).map_err(to_io_error)
+    }
+}
+
+// RGB palette & index of every pixel, if there are at most 256 colors
+fn palette(pixels: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+    let mut colors: Vec<[u8; 3]> = Vec::new();
+    let mut indices = Vec::with_capacity(pixels.len() / 4);
+    for pixel in pixels.chunks(4) {
+        let rgb = [pixel[0], pixel[1], pixel[2]];
+        let index = match colors.iter().position(|color| *color == rgb) {
+            Some(index) => index,
+            None if colors.len() < 256 => {
+                colors.push(rgb);
+                colors.len() - 1
+            }
+            None => return None
+        };
+        indices.push(index as u8);
+    }
+    Some((colors.concat(), indices))
+}
+
+fn to_rgba(color: Color) -> [u8; 4] {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    [channel(color[0]), channel(color[1]), channel(color[2]), channel(color[3])]
+}
+
+fn to_u16(size: u32) -> io::Result<u16> {
+    if size > u16::MAX as u32 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "image too large for a GIF"));
+    }
+    Ok(size as u16)
+}
+
+fn to_io_error<E: std::error::Error + Send + Sync + 'static>(err: E) -> io::Error {
+    io::Error::other(err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::settings::Settings;
+
+    fn pixel(image: &Image, x: u32, y: u32) -> [u8; 4] {
+        let index = ((y * image.width() + x) * 4) as usize;
+        let pixel = &image.pixels()[index..index + 4];
+        [pixel[0], pixel[1], pixel[2], pixel[3]]
+    }
+
+    #[test]
+    fn fill_blends_and_stays_inside() {
+        let mut image = Image::new(4, 4, [0.0, 0.0, 0.0, 1.0]);
+        image.fill(-2, -2, 4, 4, [1.0, 1.0, 1.0, 1.0]);
+        image.fill(2, 2, 10, 10, [1.0, 0.0, 0.0, 0.5]);
+        assert_eq!(pixel(&image, 1, 1), [255, 255, 255, 255]);
+        assert_eq!(pixel(&image, 2, 1), [0, 0, 0, 255]);
+        assert_eq!(pixel(&image, 3, 3), [128, 0, 0, 255]);
+    }
+
+    #[test]
+    fn blocks_are_drawn_where_they_are() {
+        let settings = Settings::default();
+        let view = Game::new(&settings, 1).view();
+        let image = render(&view, &settings.colors, 4);
+        // the board, and the score bar under it
+        assert_eq!((image.width(), image.height()), (30 * 4, 30 * 4 + 7));
+        let head = view.snakes[0][0];
+        assert_eq!(pixel(&image, head.x as u32 * 4 + 1, head.y as u32 * 4 + 1), to_rgba(settings.colors.snake));
+        let food = view.food.unwrap();
+        assert_eq!(pixel(&image, food.x as u32 * 4 + 1, food.y as u32 * 4 + 1), to_rgba(settings.colors.food));
+    }
+
+    #[test]
+    fn animations_are_gifs_of_one_size() {
+        let mut out = Vec::new();
+        {
+            let mut animation = Animation::new(&mut out, 8, 8).unwrap();
+            animation.push(&Image::new(8, 8, [1.0, 0.0, 0.0, 1.0]), 0.3).unwrap();
+            animation.push(&Image::new(8, 8, [0.0, 0.0, 1.0, 1.0]), 0.3).unwrap();
+            assert!(animation.push(&Image::new(4, 8, [0.0, 0.0, 1.0, 1.0]), 0.3).is_err());
+        }
+        assert!(out.starts_with(b"GIF89a"));
+    }
+}
@@ -0,0 +1,340 @@
+// recording of a match: the seed & settings plus every input and the tick it happened on
+// feeding the inputs back at the same ticks plays out the same match
+//
+// file format (plain text, one entry per line):
+//   snake-replay 6
+//   seed <seed>
+//   end <last tick>
+//   settings
+//   <settings file, as TOML>
+//   inputs
+//   <tick> <input>
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::game::Game;
+use crate::input::Input;
+use crate::settings::Settings;
+use crate::state::State;
+
+// first line, with the version of the rules
+// -> bumped whenever the same inputs play out differently (held paddle keys, queued turns, ticks
+//    on a fixed timestep, two snakes...), so that replays of other versions are refused, not desynced
+const HEADER: &str = "snake-replay 6";
+const FORMAT: &str = "snake-replay";
+
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub seed: u64,
+    pub settings: Settings,
+
+    // tick the recording stopped on
+    pub end: u64,
+
+    // in the order they happened
+    pub inputs: Vec<(u64, Input)>
+}
+
+// feeds the inputs of a replay back one tick at a time
+pub struct Playback<'a> {
+    replay: &'a Replay,
+    next: usize
+}
+
+impl Replay {
+    // start an empty recording
+    pub fn new(seed: u64, settings: &Settings) -> Replay {
+        Replay {
+            seed,
+            settings: settings.clone(),
+            end: 0,
+            inputs: Vec::new()
+        }
+    }
+
+    // remember an input given on the tick
+    pub fn record(&mut self, tick: u64, input: Input) {
+        self.inputs.push((tick, input));
+        self.end = self.end.max(tick);
+    }
+
+    // mark the tick the recording stops on
+    pub fn finish(&mut self, tick: u64) {
+        self.end = self.end.max(tick);
+    }
+
+    // write the replay into a file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+    // the replay as the text of its file
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "{}\nseed {}\nend {}\nsettings\n{}inputs\n",
+            HEADER, self.seed, self.end, self.settings.to_toml()
+        );
+        for (tick, input) in &self.inputs {
+            text.push_str(&format!("{} {}\n", tick, input));
+        }
+        text
+    }
+
+    // read a replay back from a file
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Replay> {
+        let text = fs::read_to_string(path)?;
+        Replay::parse(&text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    // read a replay from its text
+    pub fn parse(text: &str) -> Result<Replay, String> {
+        let mut lines = text.lines().enumerate();
+
+        match lines.next() {
+            Some((_, line)) if line.trim() == HEADER => (),
+            Some((_, line)) if line.trim().starts_with(FORMAT) => {
+                return Err(format!("'{}' is a replay of another version of the game, expected '{}'", line.trim(), HEADER));
+            }
+            _ => return Err(format!("not a replay, expected '{}' on the first line", HEADER))
+        }
+
+        let mut replay = Replay::new(0, &Settings::default());
+        let mut seed = false;
+        // lines of the settings section, until "inputs"
+        let mut settings: Option<String> = None;
+
+        for (number, line) in lines {
+            // line numbers start at 1 in error messages
+            let bad_line = |what: &str| format!("line {}: {} '{}'", number + 1, what, line);
+
+            if let Some(mut toml) = settings.take() {
+                if line.trim() != "inputs" {
+                    toml.push_str(line);
+                    toml.push('\n');
+                    settings = Some(toml);
+                    continue;
+                }
+                replay.settings = toml::from_str(&toml)
+                    .map_err(|err| format!("settings: {}", err))?;
+                replay.settings.validate().map_err(|err| err.to_string())?;
+                continue;
+            }
+
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                [] => (),
+                ["seed", value] => {
+                    replay.seed = value.parse().map_err(|_| bad_line("invalid seed"))?;
+                    seed = true;
+                }
+                ["end", tick] => {
+                    let tick = tick.parse().map_err(|_| bad_line("invalid tick"))?;
+                    replay.finish(tick);
+                }
+                ["settings"] => settings = Some(String::new()),
+                [tick, input] => {
+                    let tick: u64 = tick.parse().map_err(|_| bad_line("invalid tick"))?;
+                    let input: Input = input.parse().map_err(|err: String| bad_line(&err))?;
+                    // inputs have to be in the order they happened
+                    if replay.inputs.last().is_some_and(|(last, _)| *last > tick) {
+                        return Err(bad_line("tick goes backwards"));
+                    }
+                    replay.record(tick, input);
+                }
+                _ => return Err(bad_line("unexpected"))
+            }
+        }
+
+        if !seed {
+            return Err("replay is missing its seed".to_owned());
+        }
+        if settings.is_some() {
+            return Err("settings are not followed by 'inputs'".to_owned());
+        }
+        Ok(replay)
+    }
+
+    // start feeding the inputs from the first one
+    pub fn playback(&self) -> Playback<'_> {
+        Playback {
+            replay: self,
+            next: 0
+        }
+    }
+}
+
+impl<'a> Playback<'a> {
+    // inputs recorded on the tick, in order
+    pub fn due(&mut self, tick: u64) -> Vec<Input> {
+        let mut due = Vec::new();
+        while let Some((at, input)) = self.replay.inputs.get(self.next) {
+            if *at > tick {
+                break;
+            }
+            due.push(*input);
+            self.next += 1;
+        }
+        due
+    }
+
+    // give the game the inputs recorded on its tick
+    // -> a restart moves on to the next tick: its countdown is skipped, and the inputs of that
+    //    tick are given right away, as the match gave them right before playing it
+    pub fn give_due(&mut self, game: &mut Game) {
+        loop {
+            let tick = game.tick();
+            for input in self.due(tick) {
+                game.key_pressed(input);
+            }
+            if game.tick() == tick {
+                return;
+            }
+            if let State::Countdown(_) = game.state() {
+                game.set_state(State::Playing);
+            }
+        }
+    }
+
+    // true once the game reached the tick the recording stopped on
+    pub fn finished(&self, tick: u64) -> bool {
+        self.next == self.replay.inputs.len() && tick >= self.replay.end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autopilot::Autopilot;
+    use crate::controller::Controller;
+    use crate::objects::{Direction, Side};
+    use crate::paddle_ai::{Difficulty, PaddleAi};
+
+    fn replay() -> Replay {
+        let mut replay = Replay::new(42, &Settings::default());
+        replay.record(0, Input::Snake(Direction::Up));
+        replay.record(3, Input::PaddleUp(Side::Left));
+        replay.record(3, Input::PaddleDown(Side::Right));
+        replay.record(9, Input::Snake(Direction::Left));
+        replay.finish(12);
+        replay
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let replay = replay();
+        let parsed = Replay::parse(&replay.to_text()).unwrap();
+        assert_eq!(parsed.seed, 42);
+        assert_eq!(parsed.end, 12);
+        assert_eq!(parsed.settings, replay.settings);
+        assert_eq!(parsed.inputs, replay.inputs);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let text = replay().to_text();
+        let errors = [
+            ("", "not a replay"),
+            ("snake-replay 5\nseed 1\n", "another version"),
+            (&text.replacen(HEADER, "snake-game 6", 1), "not a replay"),
+            (&text.replacen("seed 42\n", "", 1), "missing its seed"),
+            (&text.replacen("seed 42", "seed x", 1), "invalid seed"),
+            (&text.replacen("9 snake-left", "2 snake-left", 1), "tick goes backwards"),
+            (&text.replacen("9 snake-left", "9 jump", 1), "unknown input"),
+            (&text.replacen("inputs\n", "", 1), "not followed by 'inputs'"),
+            (&text.replacen("width = 30", "width = 5", 1), "at least 12x12")
+        ];
+        for (text, error) in errors.iter() {
+            let err = Replay::parse(text).err().unwrap();
+            assert!(err.contains(error), "{:?} does not say {:?}", err, error);
+        }
+    }
+
+    #[test]
+    fn playback_feeds_inputs_on_their_tick() {
+        let replay = replay();
+        let mut playback = replay.playback();
+        assert_eq!(playback.due(0), vec![Input::Snake(Direction::Up)]);
+        assert_eq!(playback.due(2), vec![]);
+        assert_eq!(playback.due(3), vec![Input::PaddleUp(Side::Left), Input::PaddleDown(Side::Right)]);
+        assert!(!playback.finished(9));
+        assert_eq!(playback.due(9), vec![Input::Snake(Direction::Left)]);
+        assert!(!playback.finished(11));
+        assert!(playback.finished(12));
+    }
+
+    #[test]
+    fn playback_plays_the_same_match() {
+        let turns = [Direction::Up, Direction::Left, Direction::Down, Direction::Right];
+        let settings = Settings::default();
+        let mut game = Game::new(&settings, 7);
+        let mut replay = Replay::new(7, &settings);
+        let mut last_tick = None;
+        for _ in 0..2000 {
+            // (keys only on the first frame of a tick, where playback gives them)
+            if last_tick != Some(game.tick()) {
+                last_tick = Some(game.tick());
+                if game.tick().is_multiple_of(5) {
+                    let input = Input::Snake(turns[(game.tick() / 5) as usize % turns.len()]);
+                    replay.record(game.tick(), input);
+                    game.key_pressed(input);
+                }
+            }
+            game.update(0.1);
+        }
+        replay.finish(game.tick());
+
+        let replay = Replay::parse(&replay.to_text()).unwrap();
+        let mut again = Game::new(&replay.settings, replay.seed);
+        let mut playback = replay.playback();
+        while !playback.finished(again.tick()) {
+            for input in playback.due(again.tick()) {
+                again.key_pressed(input);
+            }
+            again.update(0.1);
+        }
+        assert_eq!(format!("{:?}", again.view()), format!("{:?}", game.view()));
+    }
+
+    #[test]
+    fn restarts_play_back_as_they_were_played() {
+        let settings = Settings::default();
+        let mut game = Game::new(&settings, 11);
+        let mut replay = Replay::new(11, &settings);
+        let mut players: Vec<Box<dyn Controller>> = vec![
+            Box::new(Autopilot::new()),
+            Box::new(PaddleAi::new(Side::Left, Difficulty::Hard, 1)),
+            Box::new(PaddleAi::new(Side::Right, Difficulty::Hard, 2))
+        ];
+        // as in the window: the computer answers right before every tick, once the countdown
+        // after a restart is over
+        for tick in 0..1200 {
+            if tick % 300 == 299 {
+                replay.record(game.tick(), Input::Restart);
+                game.key_pressed(Input::Restart);
+                game.set_state(State::Playing);
+            }
+            for player in &mut players {
+                for input in player.control(&game) {
+                    replay.record(game.tick(), input);
+                    game.key_pressed(input);
+                }
+            }
+            game.advance();
+        }
+        replay.finish(game.tick());
+
+        // as in an export
+        let mut again = Game::new(&replay.settings, replay.seed);
+        let mut playback = replay.playback();
+        loop {
+            playback.give_due(&mut again);
+            if playback.finished(again.tick()) {
+                break;
+            }
+            again.advance();
+        }
+        assert_eq!(again.view(), game.view());
+        assert_eq!(again.tick(), game.tick());
+    }
+}
@@ -0,0 +1,480 @@
+// a match between two peers with rollback netcode (GGPO-style): each peer plays its own inputs
+// right away and guesses the other's, so neither waits on the network
+// -> the guess is "no new input" (turns & held paddle keys carry on by themselves in the game);
+//    when the other peer's inputs come in and differ from the guess, the game goes back to its
+//    snapshot from before that tick and plays the ticks since again with the real inputs
+// both peers play the same ticks with the same inputs in the same order (the host's first),
+// so the matches end up the same whatever the network did
+//
+// the peer that waits ("host") picks the seed & settings, the one that joins picks a part
+// (Role) and the host plays every other part
+//
+// messages are JSON, one per packet:
+//   joining peer -> host   {"Hello":{"role":"left-paddle"}}                       until welcomed
+//   host -> joining peer   {"Welcome":{"seed":42,"settings":{...}}} or {"Refused":{"reason":"..."}}
+//   both ways              {"Inputs":{"ack":120,"start":118,"inputs":[[],["left-paddle-hold-up"],...]}}
+//                          (the sender's inputs from tick start on, none lost even if packets are,
+//                          and how many ticks of the other's inputs it has)
+//                          {"Bye":null} on leaving
+use std::collections::{BTreeMap, VecDeque};
+use std::io;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::Game;
+use crate::input::Input;
+use crate::link::Transport;
+use crate::net::Role;
+use crate::settings::Settings;
+use crate::state::{State, COUNTDOWN};
+use crate::view::Motion;
+
+// ticks a peer plays ahead of the other's last known inputs at most, and so the ticks it may
+// have to play again (it waits for the other peer beyond that)
+pub const MAX_ROLLBACK: u64 = 8;
+
+// ticks a single update plays at most, as in Game
+const MAX_CATCH_UP: usize = 5;
+
+// seconds without a packet before the other peer counts as gone
+const TIMEOUT: f64 = 5.0;
+
+// seconds between two hellos of a joining peer, and how long it keeps trying
+const HELLO_PERIOD: f64 = 0.2;
+const JOIN_TIMEOUT: f64 = 5.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Message {
+    Hello { role: String },
+    // (boxed, it is much bigger than the messages sent every update)
+    Welcome { seed: u64, settings: Box<Settings> },
+    Refused { reason: String },
+    Inputs { ack: u64, start: u64, inputs: Vec<Vec<String>> },
+    Bye
+}
+
+pub struct Rollback {
+    link: Box<dyn Transport>,
+    // true on the peer that waited for the other
+    hosting: bool,
+    // part the joining peer plays, once known
+    role: Option<Role>,
+
+    // the game as it is after the ticks played so far
+    game: Game,
+    // ticks played so far
+    tick: u64,
+    // game before each tick not yet confirmed, oldest first
+    snapshots: VecDeque<(u64, Game)>,
+    // inputs of this peer for every tick it still needs them, and those for the next tick
+    local: BTreeMap<u64, Vec<Input>>,
+    pending: Vec<Input>,
+    // inputs of the other peer for the ticks it sent
+    remote: BTreeMap<u64, Vec<Input>>,
+    // ticks of the other peer's inputs known (all of them up to this one)
+    confirmed: u64,
+    // ticks of this peer's inputs the other peer has
+    acked: u64,
+
+    // seconds since the start & since the last tick
+    time: f64,
+    waiting_time: f64,
+    // time the last packet came in
+    heard: f64,
+    // ticks played again because a guess was wrong, for the side panel
+    rolled_back: u64,
+    // true once the other peer said it leaves
+    left: bool
+}
+
+// one tick of the match: the inputs of both peers (the host's first), then the clock moves on by
+// one moving period (a tick of play, or of the countdown)
+// (a restart only counts once the round is over, whichever peer asks first)
+fn simulate(game: &mut Game, inputs: &[Input]) {
+    for input in inputs {
+        if *input == Input::Restart && !(game.game_over() || game.game_win()) {
+            continue;
+        }
+        game.key_pressed(*input);
+    }
+    let period = game.settings().speed.moving_period;
+    game.update(period);
+}
+
+// rollback design
+impl Rollback {
+    // wait for a peer to join a match on the settings & seed
+    // (nothing moves until it does)
+    pub fn host(link: Box<dyn Transport>, settings: &Settings, seed: u64) -> Rollback {
+        Rollback::new(link, true, None, Game::new(settings, seed))
+    }
+
+    // join the match of a waiting peer to play a part of it
+    // -> the match on the host's settings & seed, or why it could not be joined
+    pub fn join(mut link: Box<dyn Transport>, role: Role) -> io::Result<Rollback> {
+        let start = Instant::now();
+        let mut last_hello = f64::NEG_INFINITY;
+        loop {
+            let now = start.elapsed().as_secs_f64();
+            if now > JOIN_TIMEOUT {
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "the other peer did not answer"));
+            }
+            if now - last_hello >= HELLO_PERIOD {
+                send(&mut link, &Message::Hello { role: role.to_string() }, now);
+                last_hello = now;
+            }
+            for packet in link.receive(now) {
+                match serde_json::from_slice(&packet) {
+                    Ok(Message::Welcome { seed, settings }) => {
+                        settings.validate().map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+                        let game = Game::new(&settings, seed);
+                        return Ok(Rollback::new(link, false, Some(role), game));
+                    }
+                    Ok(Message::Refused { reason }) => {
+                        return Err(io::Error::new(io::ErrorKind::PermissionDenied, reason));
+                    }
+                    _ => ()
+                }
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn new(link: Box<dyn Transport>, hosting: bool, role: Option<Role>, mut game: Game) -> Rollback {
+        game.set_state(State::Countdown(COUNTDOWN));
+        Rollback {
+            link,
+            hosting,
+            role,
+            game,
+            tick: 0,
+            snapshots: VecDeque::new(),
+            local: BTreeMap::new(),
+            pending: Vec::new(),
+            remote: BTreeMap::new(),
+            confirmed: 0,
+            acked: 0,
+            time: 0.0,
+            waiting_time: 0.0,
+            heard: 0.0,
+            rolled_back: 0,
+            left: false
+        }
+    }
+
+    // the game as this peer sees it
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    // where the snakes & the food were before the last tick, and how far the clock is towards
+    // the next one
+    pub fn motion(&self) -> Motion {
+        let period = self.game.settings().speed.moving_period;
+        Motion {
+            progress: (self.waiting_time / period).clamp(0.0, 1.0),
+            ..self.game.motion()
+        }
+    }
+
+    // the part the joining peer plays, none while the host waits for it
+    pub fn role(&self) -> Option<Role> {
+        self.role
+    }
+
+    // true on the peer that waited for the other
+    pub fn hosting(&self) -> bool {
+        self.hosting
+    }
+
+    // true while the host waits for a peer
+    pub fn waiting(&self) -> bool {
+        self.role.is_none()
+    }
+
+    // true if the input is for a part this peer plays
+    // (the joining peer's part, or every other one on the host; either may restart a round)
+    pub fn plays(&self, input: Input) -> bool {
+        let role = match self.role {
+            Some(role) => role,
+            None => return false
+        };
+        input == Input::Restart || role.owns(input) != self.hosting
+    }
+
+    // ticks played ahead of the other peer's last known inputs
+    pub fn ahead(&self) -> u64 {
+        self.tick.saturating_sub(self.confirmed)
+    }
+
+    // ticks played again so far because the other peer's inputs were guessed wrong
+    pub fn rolled_back(&self) -> u64 {
+        self.rolled_back
+    }
+
+    // true once the other peer left, or was not heard of for a while
+    pub fn lost(&self) -> bool {
+        self.left || (!self.waiting() && self.time - self.heard > TIMEOUT)
+    }
+
+    // an input of this peer, played on the next tick
+    pub fn give(&mut self, input: Input) {
+        if self.plays(input) {
+            self.pending.push(input);
+        }
+    }
+
+    // tell the other peer this one leaves
+    pub fn leave(&mut self) {
+        send(&mut self.link, &Message::Bye, self.time);
+    }
+
+    // move the match on by the time since the last frame: a tick every moving period, unless
+    // this peer is MAX_ROLLBACK ticks ahead of the other (then it waits for its inputs)
+    pub fn update(&mut self, delta_time: f64) {
+        self.time += delta_time;
+        self.receive();
+        if self.waiting() {
+            return;
+        }
+
+        let period = self.game.settings().speed.moving_period;
+        self.waiting_time += delta_time;
+        let mut ticks = 0;
+        while self.waiting_time >= period {
+            if ticks == MAX_CATCH_UP {
+                self.waiting_time %= period;
+                break;
+            }
+            if self.ahead() >= MAX_ROLLBACK {
+                self.waiting_time = period;
+                break;
+            }
+            self.waiting_time -= period;
+            self.advance();
+            ticks += 1;
+        }
+        self.send_inputs();
+    }
+
+    // play the next tick, guessing the other peer's inputs if they are not in yet
+    fn advance(&mut self) {
+        let tick = self.tick;
+        self.local.insert(tick, std::mem::take(&mut self.pending));
+        self.snapshots.push_back((tick, self.game.clone()));
+        let inputs = self.inputs(tick);
+        simulate(&mut self.game, &inputs);
+        self.tick += 1;
+        self.forget();
+    }
+
+    // inputs of both peers on a tick, the host's first
+    // (the other peer's are guessed to be none until they are known)
+    fn inputs(&self, tick: u64) -> Vec<Input> {
+        let local = self.local.get(&tick).into_iter().flatten();
+        let remote = self.remote.get(&tick).into_iter().flatten();
+        if self.hosting {
+            local.chain(remote).copied().collect()
+        } else {
+            remote.chain(local).copied().collect()
+        }
+    }
+
+    // go back to the game before a tick & play the ticks since again, with what is known now
+    fn roll_back(&mut self, from: u64) {
+        let index = match self.snapshots.iter().position(|(tick, _)| *tick == from) {
+            Some(index) => index,
+            None => return
+        };
+        self.game = self.snapshots[index].1.clone();
+        self.snapshots.truncate(index);
+        for tick in from..self.tick {
+            self.snapshots.push_back((tick, self.game.clone()));
+            let inputs = self.inputs(tick);
+            simulate(&mut self.game, &inputs);
+        }
+        self.rolled_back += self.tick - from;
+    }
+
+    // drop what no tick can go back to any more
+    // (snapshots & inputs before the other peer's known ticks, and inputs the other peer has)
+    fn forget(&mut self) {
+        while self.snapshots.front().is_some_and(|(tick, _)| *tick < self.confirmed) {
+            self.snapshots.pop_front();
+        }
+        let keep = self.confirmed.min(self.acked);
+        self.local = self.local.split_off(&keep);
+        self.remote = self.remote.split_off(&self.confirmed.min(self.tick));
+    }
+
+    // read what the other peer sent
+    fn receive(&mut self) {
+        let mut wrong_from: Option<u64> = None;
+        for packet in self.link.receive(self.time) {
+            let message: Message = match serde_json::from_slice(&packet) {
+                Ok(message) => message,
+                Err(_) => continue
+            };
+            self.heard = self.time;
+            match message {
+                Message::Hello { role } if self.hosting => self.welcome(&role),
+                Message::Inputs { ack, start, inputs } if !self.waiting() => {
+                    self.acked = self.acked.max(ack.min(self.tick));
+                    for (tick, names) in (start..).zip(inputs) {
+                        // (ticks already known, or beyond a gap, wait for a later packet)
+                        if tick != self.confirmed {
+                            continue;
+                        }
+                        let inputs: Vec<Input> = names.iter()
+                            .filter_map(|name| name.parse().ok())
+                            .filter(|input| self.remote_plays(*input))
+                            .collect();
+                        // (a tick played on a guess of no inputs was right if there were none)
+                        if tick < self.tick && !inputs.is_empty() {
+                            wrong_from = Some(wrong_from.map_or(tick, |from| from.min(tick)));
+                        }
+                        self.remote.insert(tick, inputs);
+                        self.confirmed += 1;
+                    }
+                }
+                Message::Bye => self.left = true,
+                _ => ()
+            }
+        }
+        if let Some(from) = wrong_from {
+            self.roll_back(from);
+        }
+        self.forget();
+    }
+
+    // let a peer in for a part, or tell it why not
+    // (a peer asking again, because the welcome got lost, is welcomed again)
+    fn welcome(&mut self, role: &str) {
+        let answer = match role.parse::<Role>() {
+            Ok(role) if self.role.is_some_and(|other| other != role) => Err("the match is full".to_owned()),
+            Ok(role) if !role.in_game(&self.game) => Err(format!("there is no {} in this match", role)),
+            Ok(role) => Ok(role),
+            Err(err) => Err(err)
+        };
+        let message = match answer {
+            Ok(role) => {
+                self.role = Some(role);
+                Message::Welcome { seed: self.game.seed(), settings: Box::new(self.game.settings().clone()) }
+            }
+            Err(reason) => Message::Refused { reason }
+        };
+        send(&mut self.link, &message, self.time);
+    }
+
+    // true if the input is for a part the other peer plays
+    fn remote_plays(&self, input: Input) -> bool {
+        match self.role {
+            Some(role) => input == Input::Restart || role.owns(input) == self.hosting,
+            None => false
+        }
+    }
+
+    // this peer's inputs the other peer does not have yet, and the ticks of its inputs known here
+    fn send_inputs(&mut self) {
+        let inputs = (self.acked..self.tick)
+            .map(|tick| {
+                let inputs = self.local.get(&tick).into_iter().flatten();
+                inputs.map(|input| input.to_string()).collect()
+            })
+            .collect();
+        let message = Message::Inputs { ack: self.confirmed, start: self.acked, inputs };
+        send(&mut self.link, &message, self.time);
+    }
+}
+
+// a message as a packet
+fn send(link: &mut Box<dyn Transport>, message: &Message, now: f64) {
+    if let Ok(packet) = serde_json::to_vec(message) {
+        link.send(&packet, now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    use crate::autopilot::Autopilot;
+    use crate::controller::Controller;
+    use crate::link::{local_pair, Conditions, Simulated};
+    use crate::objects::Side;
+    use crate::paddle_ai::{Difficulty, PaddleAi};
+
+    const BAD_NETWORK: Conditions = Conditions { latency: 0.08, jitter: 0.03, loss: 0.15 };
+
+    // the host playing every part but the right paddle, the joining peer that paddle, both
+    // through a bad network
+    fn peers(settings: &Settings) -> (Rollback, Rollback) {
+        let (host_end, join_end) = local_pair();
+        let mut host = Rollback::host(Box::new(Simulated::new(host_end, BAD_NETWORK, 1)), settings, 42);
+
+        // (joining waits for the welcome, the host has to answer meanwhile)
+        let joined = Arc::new(AtomicBool::new(false));
+        let hosting = {
+            let joined = joined.clone();
+            thread::spawn(move || {
+                while !joined.load(Ordering::SeqCst) {
+                    host.update(0.005);
+                    thread::sleep(Duration::from_millis(5));
+                }
+                host
+            })
+        };
+        let link = Box::new(Simulated::new(join_end, BAD_NETWORK, 2));
+        let peer = Rollback::join(link, Role::Paddle(Side::Right));
+        joined.store(true, Ordering::SeqCst);
+        (hosting.join().unwrap(), peer.unwrap())
+    }
+
+    #[test]
+    fn peers_end_up_in_the_same_game_over_a_bad_network() {
+        let (mut host, mut peer) = peers(&Settings::default());
+        assert_eq!(peer.role(), Some(Role::Paddle(Side::Right)));
+        assert_eq!(peer.game().seed(), 42);
+
+        // the computer plays every part, answering once per tick as in the window
+        let mut snake = Autopilot::new();
+        let mut left = PaddleAi::new(Side::Left, Difficulty::Hard, 3);
+        let mut right = PaddleAi::new(Side::Right, Difficulty::Normal, 4);
+        let (mut host_tick, mut peer_tick) = (None, None);
+        let mut compared = 0;
+        for _ in 0..6000 {
+            if host_tick != Some(host.game().tick()) {
+                host_tick = Some(host.game().tick());
+                for input in snake.control(host.game()).into_iter().chain(left.control(host.game())) {
+                    host.give(input);
+                }
+                if host.game().game_over() || host.game().game_win() {
+                    host.give(Input::Restart);
+                }
+            }
+            if peer_tick != Some(peer.game().tick()) {
+                peer_tick = Some(peer.game().tick());
+                for input in right.control(peer.game()) {
+                    peer.give(input);
+                }
+            }
+            host.update(0.02);
+            peer.update(0.02);
+
+            // once each peer has every input of the other, both played the same ticks
+            if host.ahead() == 0 && peer.ahead() == 0 {
+                assert_eq!(host.game().tick(), peer.game().tick());
+                assert_eq!(host.game().view(), peer.game().view());
+                compared += 1;
+            }
+        }
+        assert!(!host.lost() && !peer.lost());
+        assert!(host.game().tick() > 300, "the match hardly moved ({} ticks)", host.game().tick());
+        assert!(compared > 1000, "the peers were seldom both up to date ({} times)", compared);
+        assert!(host.rolled_back() + peer.rolled_back() > 0, "no guess was ever wrong");
+    }
+}
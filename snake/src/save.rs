@@ -0,0 +1,123 @@
+// whole games on disk, to pick a match up again exactly where it was left
+// (snakes, paddles, enemies, food, scores, timers & the random number generator: a loaded game
+// plays on as the saved one would have)
+//
+// two formats, told apart by the file name:
+//   .json        the Game as JSON, to read or edit by hand
+//   any other    "snake-save 1" on a line, then the Game in bincode (under a kilobyte)
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::game::Game;
+
+// first line of a binary save, with the version of the format
+const MAGIC: &[u8] = b"snake-save 1\n";
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Format {
+    Json,
+    Binary
+}
+
+// format design
+impl Format {
+    // the format of a file, from its name
+    pub fn of<P: AsRef<Path>>(path: P) -> Format {
+        match path.as_ref().extension().and_then(|extension| extension.to_str()) {
+            Some("json") => Format::Json,
+            _ => Format::Binary
+        }
+    }
+}
+
+// where quick saves go, if the system has a data directory
+pub fn quick_save_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("snake").join("quicksave.bin"))
+}
+
+// a game in a format
+// (every part of a game serializes, so this cannot fail)
+pub fn to_bytes(game: &Game, format: Format) -> Vec<u8> {
+    match format {
+        Format::Json => {
+            let mut bytes = serde_json::to_vec_pretty(game).unwrap();
+            bytes.push(b'\n');
+            bytes
+        }
+        Format::Binary => {
+            let mut bytes = MAGIC.to_vec();
+            bytes.extend(bincode::serialize(game).unwrap());
+            bytes
+        }
+    }
+}
+
+// a game back from a format
+// -> refused if it is not a saved game, or its settings are out of range
+pub fn from_bytes(bytes: &[u8], format: Format) -> Result<Game, String> {
+    let game: Game = match format {
+        Format::Json => serde_json::from_slice(bytes).map_err(|err| err.to_string())?,
+        Format::Binary => {
+            let body = bytes.strip_prefix(MAGIC).ok_or("not a saved game (or one of another version)")?;
+            bincode::deserialize(body).map_err(|err| err.to_string())?
+        }
+    };
+    game.settings().validate().map_err(|err| err.to_string())?;
+    Ok(game)
+}
+
+// write a game, creating its directory if needed
+pub fn save<P: AsRef<Path>>(game: &Game, path: P) -> io::Result<()> {
+    let path = path.as_ref();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, to_bytes(game, Format::of(path)))
+}
+
+// read a game
+pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Game> {
+    let path = path.as_ref();
+    let bytes = fs::read(path)?;
+    from_bytes(&bytes, Format::of(path)).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autopilot::Autopilot;
+    use crate::controller::Controller;
+    use crate::settings::Settings;
+
+    fn play(game: &mut Game, ticks: u64) {
+        let mut autopilot = Autopilot::new();
+        for _ in 0..ticks {
+            for input in autopilot.control(game) {
+                game.key_pressed(input);
+            }
+            game.advance();
+        }
+    }
+
+    #[test]
+    fn a_loaded_game_plays_on_as_the_saved_one() {
+        let mut game = Game::new(&Settings::default(), 3);
+        play(&mut game, 200);
+        for format in [Format::Json, Format::Binary].iter() {
+            let mut loaded = from_bytes(&to_bytes(&game, *format), *format).unwrap();
+            let mut saved = game.clone();
+            play(&mut saved, 300);
+            play(&mut loaded, 300);
+            assert_eq!(to_bytes(&loaded, Format::Binary), to_bytes(&saved, Format::Binary));
+        }
+    }
+
+    #[test]
+    fn other_files_are_refused() {
+        assert_eq!(Format::of("game.json"), Format::Json);
+        assert_eq!(Format::of("game.bin"), Format::Binary);
+        assert!(from_bytes(b"snake-save 0\n", Format::Binary).is_err());
+        assert!(from_bytes(b"{}", Format::Json).is_err());
+    }
+}
@@ -0,0 +1,954 @@
+// one match as both front ends play it: the game, the replay being played back or recorded,
+// the computer players, and the players over the network (or the other peer of a rollback match)
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+
+use snake::highscores::NAME_LENGTH;
+use snake::input::MenuInput;
+use snake::keymap::Control;
+use snake::keys::{menu_input_for_key, Key};
+use snake::net::{Client, Event, Frame, Host, Role};
+use snake::raster::render;
+use snake::replay::Playback;
+use snake::rollback::Rollback;
+use snake::save;
+use snake::settings::Colors;
+use snake::state::{select, Choices, MenuItem, Mode, TitleItem, COUNTDOWN};
+use snake::{
+    Animation, Autopilot, Bot, Controller, Difficulty, Game, HighScore, HighScores, Input, KeyMap, PaddleAi, Replay, Settings,
+    Motion, Side, State, View
+};
+
+use crate::cli::Options;
+
+pub struct Session<'a> {
+    pub game: Game,
+
+    // replay being played back, or recording of this match
+    playback: Option<Playback<'a>>,
+    recording: Option<Replay>,
+
+    // what the title screen picked, and the paddles the command line gave a difficulty
+    choices: Choices,
+    paddle_ai: Vec<(Option<Side>, Difficulty)>,
+
+    // keys of every control & the file they are kept in (none if there is nowhere to keep them)
+    key_map: KeyMap,
+    key_map_path: Option<PathBuf>,
+    // keys held down, if the front end tells when they go up
+    keys_down: Option<Vec<Key>>,
+    // file quick saves go to (none if there is nowhere to keep them)
+    quick_save_path: Option<PathBuf>,
+
+    // computer players for the paddles & the snake, and what they play
+    controllers: Vec<Box<dyn Controller>>,
+    ai_sides: Vec<Side>,
+    autopilot: bool,
+    // programs playing parts of the game, for as long as the session lasts
+    bots: Vec<Bot>,
+
+    // tick the computer players last answered on
+    last_tick: u64,
+    // tick the bots were last asked about
+    asked_tick: Option<u64>,
+
+    colors: Colors,
+    // animation of the match being saved, if any
+    gif: Option<GifCapture>,
+
+    // best scores & the file they are kept in (none if there is nowhere to keep them)
+    high_scores: Option<(PathBuf, HighScores)>,
+    // place of the last score added to the table
+    new_rank: Option<usize>,
+    // true once the end of the current round was looked at
+    round_ended: bool,
+
+    // players & spectators who joined this match over the network, if it is hosted
+    host: Option<Host>,
+    // the match of another player this one joined or watches, if any
+    // (the game is only there to go by its settings, the host plays the match)
+    remote: Option<Remote>,
+    // the match played with another peer, each running the game, if any
+    // (the game is only there to go by its settings & state, the rollback has the match)
+    rollback: Option<Rollback>
+}
+
+// a match hosted by another player, as last seen
+struct Remote {
+    client: Client,
+    frame: Option<Frame>,
+    // seconds since the host's last tick came in
+    since_tick: f64
+}
+
+// frames of a match going into a GIF
+// -> a frame is written once the view changes, with the time it stayed up
+struct GifCapture {
+    path: PathBuf,
+    animation: Animation<BufWriter<File>>,
+    block: u32,
+    shown: Option<(View, f64)>
+}
+
+// pixels per block of screenshots (as in the window)
+const SCREENSHOT_BLOCK: u32 = 25;
+
+impl<'a> Session<'a> {
+    // set up a match, or the playback of a replay
+    pub fn new(options: &Options, settings: &Settings, seed: u64, replay: Option<&'a Replay>) -> Session<'a> {
+        let game = Game::new(settings, seed);
+        let playback = replay.map(Replay::playback);
+        let recording = options.record.as_ref().map(|_| Replay::new(seed, settings));
+
+        // what the command line & the settings ask for is picked on the title screen already
+        let mode = match (options.autopilot, !options.paddle_ai.is_empty()) {
+            _ if settings.rules.snakes > 1 => Mode::TwoSnakes,
+            (false, false) => Mode::Versus,
+            (false, true) => Mode::Snake,
+            (true, false) => Mode::Paddles,
+            (true, true) => Mode::Watch
+        };
+        let difficulty = options.paddle_ai.last()
+            .map(|(_, difficulty)| *difficulty)
+            .unwrap_or(Difficulty::Normal);
+
+        let (key_map_path, key_map) = load_key_map(options.keys.as_deref());
+        let last_tick = game.tick();
+        let mut session = Session {
+            game,
+            playback,
+            recording,
+            choices: Choices { mode, difficulty },
+            paddle_ai: options.paddle_ai.clone(),
+            key_map,
+            key_map_path,
+            keys_down: None,
+            quick_save_path: options.quick_save.clone().or_else(save::quick_save_path),
+            controllers: Vec::new(),
+            ai_sides: Vec::new(),
+            autopilot: false,
+            bots: Vec::new(),
+            last_tick,
+            asked_tick: None,
+            colors: settings.colors.clone(),
+            gif: None,
+            high_scores: load_high_scores(),
+            new_rank: None,
+            round_ended: false,
+            host: None,
+            remote: None,
+            rollback: None
+        };
+
+        // a match starts on the title screen, a replay right away
+        // (their inputs are in the replay already when playing one back)
+        if session.playback.is_none() {
+            session.set_up_players();
+            session.game.set_state(State::Title(0));
+        }
+        session
+    }
+
+    // computer players for the mode picked on the title screen
+    // (difficulties given per side on the command line hold until another one is picked)
+    fn set_up_players(&mut self) {
+        self.controllers.clear();
+        self.ai_sides.clear();
+        // (parts remote players joined for, and bots, stay theirs)
+        self.autopilot = self.choices.mode.autopilot() && !self.remote_plays(Role::Snake) && !self.bot_plays(Role::Snake);
+        if self.autopilot {
+            self.controllers.push(Box::new(Autopilot::new()));
+        }
+        if !self.choices.mode.paddle_ai() {
+            return;
+        }
+
+        let seed = self.game.seed();
+        for (index, paddle) in self.game.settings().paddles.iter().enumerate() {
+            if self.remote_plays(Role::Paddle(paddle.side)) || self.bot_plays(Role::Paddle(paddle.side)) {
+                continue;
+            }
+            let difficulty = self.paddle_ai.iter()
+                .rev()
+                .find(|(side, _)| side.is_none() || *side == Some(paddle.side))
+                .map(|(_, difficulty)| *difficulty)
+                .unwrap_or(self.choices.difficulty);
+            let ai_seed = seed.wrapping_add(index as u64 + 1);
+            self.controllers.push(Box::new(PaddleAi::new(paddle.side, difficulty, ai_seed)));
+            self.ai_sides.push(paddle.side);
+        }
+    }
+
+    // let other players join the match, each playing a part nobody else plays, and spectators watch it
+    // (only spectators, for a rollback match: its two players are the peers)
+    pub fn host(&mut self, host: Host) {
+        self.host = Some(host);
+    }
+
+    // play a part of a match hosted by another player, or watch it (a client without a part)
+    // -> no computer players nor menus here: the host has them
+    pub fn join(&mut self, client: Client) {
+        self.controllers.clear();
+        self.ai_sides.clear();
+        self.autopilot = false;
+        self.remote = Some(Remote { client, frame: None, since_tick: 0.0 });
+        self.game.set_state(State::Playing);
+    }
+
+    // play a match with another peer over rollback netcode
+    // -> no computer players nor menus: the match starts once the other peer is there
+    pub fn play_rollback(&mut self, rollback: Rollback) {
+        self.controllers.clear();
+        self.ai_sides.clear();
+        self.autopilot = false;
+        self.rollback = Some(rollback);
+        self.game.set_state(State::Playing);
+    }
+
+    // let a bot play its part, whatever the mode
+    pub fn add_bot(&mut self, bot: Bot) {
+        self.bots.push(bot);
+        self.set_up_players();
+    }
+
+    // true if a bot plays the part
+    fn bot_plays(&self, role: Role) -> bool {
+        self.bots.iter().any(|bot| bot.role() == role)
+    }
+
+    // true if a remote player plays the part
+    fn remote_plays(&self, role: Role) -> bool {
+        self.host.as_ref().is_some_and(|host| host.roles().any(|other| other == role))
+    }
+
+    // true if the computer (or a bot) plays the part
+    fn computer_plays(&self, role: Role) -> bool {
+        if self.bot_plays(role) {
+            return true;
+        }
+        match role {
+            Role::Snake => self.autopilot,
+            Role::SecondSnake => false,
+            Role::Paddle(side) => self.ai_sides.contains(&side)
+        }
+    }
+
+    // what the board looks like: the game's, or the host's when playing a joined match
+    pub fn view(&self) -> View {
+        if let Some(rollback) = &self.rollback {
+            return rollback.game().view();
+        }
+        match &self.remote {
+            Some(Remote { frame: Some(frame), .. }) => frame.view.clone(),
+            _ => self.game.view()
+        }
+    }
+
+    // where the moving parts were, for drawing them in between
+    pub fn motion(&self) -> Motion {
+        if let Some(rollback) = &self.rollback {
+            return rollback.motion();
+        }
+        match &self.remote {
+            // (the host leaves the progress out, it goes by the time since its last tick)
+            Some(Remote { frame: Some(frame), since_tick, .. }) => Motion {
+                progress: (since_tick / self.game.settings().speed.moving_period).clamp(0.0, 1.0),
+                ..frame.motion.clone()
+            },
+            _ => self.game.motion()
+        }
+    }
+
+    // a board with a snake for every player of the mode picked on the title screen
+    // -> a new game on other settings, and the recording starts over with them
+    fn set_up_game(&mut self) {
+        let snakes = self.choices.mode.snakes();
+        if self.game.settings().rules.snakes == snakes {
+            return;
+        }
+        let mut settings = self.game.settings().clone();
+        settings.rules.snakes = snakes;
+        let seed = self.game.seed();
+        self.game = Game::new(&settings, seed);
+        self.last_tick = self.game.tick();
+        self.asked_tick = None;
+        if let Some(recording) = &mut self.recording {
+            *recording = Replay::new(seed, &settings);
+        }
+    }
+
+    // save every frame of the match into a GIF, block pixels per block
+    pub fn capture_gif(&mut self, path: &Path, block: u32) -> io::Result<()> {
+        let image = render(&self.view(), &self.colors, block);
+        self.gif = Some(GifCapture {
+            path: path.to_owned(),
+            animation: Animation::create(path, image.width(), image.height())?,
+            block,
+            shown: None
+        });
+        Ok(())
+    }
+
+    // save what the game looks like right now into a PNG, named after the seed & tick
+    pub fn screenshot(&self) -> io::Result<PathBuf> {
+        let path = PathBuf::from(format!("snake-{}-{}.png", self.game.seed(), self.game.tick()));
+        render(&self.view(), &self.colors, SCREENSHOT_BLOCK).save_png(&path)?;
+        Ok(path)
+    }
+
+    // a key pressed by the player: menus & initials, or inputs for the game
+    // (a replay can only be paused or left, and whatever the computer plays is left alone)
+    pub fn key_pressed(&mut self, key: Key) {
+        // (keys repeated by the system while held only count once)
+        if let Some(down) = &mut self.keys_down {
+            if down.contains(&key.lowercase()) {
+                return;
+            }
+            down.push(key.lowercase());
+        }
+
+        // a rollback match: the keys of the parts played, Enter starts the next round, Esc leaves
+        if let Some(rollback) = &mut self.rollback {
+            let over = rollback.game().game_over() || rollback.game().game_win();
+            if key == Key::Escape {
+                rollback.leave();
+                self.game.set_state(State::Quit);
+            } else if over && menu_input_for_key(key) == Some(MenuInput::Select) {
+                self.give(Input::Restart);
+            } else if let Some(input) = self.player_input(key) {
+                self.give(self.held(input));
+            }
+            return;
+        }
+
+        // a joined match: the keys of the part played, Esc leaves
+        if self.remote.is_some() {
+            if key == Key::Escape {
+                self.game.set_state(State::Quit);
+            } else if let Some(input) = self.player_input(key) {
+                self.give(self.held(input));
+            }
+            return;
+        }
+
+        // (a replay plays on its own inputs, it cannot be saved nor replaced)
+        if self.playback.is_none() && self.quick_key(key) {
+            return;
+        }
+
+        match self.game.state().clone() {
+            State::Title(selected) => self.title_key(selected, key),
+            State::Controls { selected, .. } => self.controls_key(selected, key),
+            State::Rebinding(index) => self.rebind(index, key),
+            State::HighScores => {
+                if let Some(MenuInput::Select) | Some(MenuInput::Back) = menu_input_for_key(key) {
+                    self.game.set_state(State::Title(TitleItem::HighScores.index()));
+                }
+            }
+            State::Countdown(_) | State::Playing if self.key_map.pauses(key) => self.game.set_state(State::Paused(0)),
+            State::Countdown(_) => (),
+            State::Playing => {
+                if self.playback.is_some() {
+                    return;
+                }
+                if let Some(input) = self.player_input(key) {
+                    self.give(self.held(input));
+                }
+            }
+            State::Paused(selected) => self.menu_key(selected, key),
+            State::GameOver | State::Win if self.playback.is_some() => {
+                if key == Key::Escape {
+                    self.game.set_state(State::Quit);
+                }
+            }
+            State::GameOver | State::Win => match menu_input_for_key(key) {
+                Some(MenuInput::Select) => self.give(Input::Restart),
+                Some(MenuInput::Back) => {
+                    self.give(Input::Restart);
+                    self.game.set_state(State::Title(0));
+                }
+                _ => ()
+            },
+            State::HighScoreEntry { mut name, won } => {
+                match key {
+                    Key::Char(c) if name.len() < NAME_LENGTH => name.push(c.to_ascii_uppercase()),
+                    Key::Backspace => {
+                        name.pop();
+                    }
+                    Key::Enter if !name.is_empty() => {
+                        self.add_high_score(&name);
+                        self.game.set_state(State::round_end(won));
+                        return;
+                    }
+                    _ => ()
+                }
+                self.game.set_state(State::HighScoreEntry { name, won });
+            }
+            State::Quit => ()
+        }
+    }
+
+    // a key going up, from front ends that see it (see track_key_releases)
+    // -> the paddle stops, or goes the way of its other key if that one is still held
+    pub fn key_released(&mut self, key: Key) {
+        let key = key.lowercase();
+        match &mut self.keys_down {
+            Some(down) => down.retain(|other| *other != key),
+            None => return
+        }
+        if self.playback.is_some() {
+            return;
+        }
+
+        let side = match self.player_input(key) {
+            Some(Input::PaddleUp(side)) | Some(Input::PaddleDown(side)) => side,
+            _ => return
+        };
+        let still_held = self.keys_down.iter().flatten().find_map(|other| match self.player_input(*other) {
+            Some(Input::PaddleUp(other_side)) if other_side == side => Some(Input::HoldUp(side)),
+            Some(Input::PaddleDown(other_side)) if other_side == side => Some(Input::HoldDown(side)),
+            _ => None
+        });
+        self.give(still_held.unwrap_or(Input::Release(side)));
+    }
+
+    // the front end tells when keys go up: held paddle keys keep the paddles moving
+    pub fn track_key_releases(&mut self) {
+        self.keys_down = Some(Vec::new());
+    }
+
+    // the input a key gives, if the player plays that part of the game
+    // (the part a joined match was joined for, those of this peer in a rollback match,
+    // or those neither the computer nor remote players play)
+    fn player_input(&self, key: Key) -> Option<Input> {
+        let input = self.key_map.input(key)?;
+        let role = Role::of(input)?;
+        let plays = match (&self.remote, &self.rollback) {
+            (Some(remote), _) => Some(role) == remote.client.role(),
+            (None, Some(rollback)) => role.in_game(rollback.game()) && rollback.plays(input),
+            (None, None) => role.in_game(&self.game) && !self.computer_plays(role) && !self.remote_plays(role)
+        };
+        Some(input).filter(|_| plays)
+    }
+
+    // paddles keep moving while their key is held, if the front end says when it goes up
+    fn held(&self, input: Input) -> Input {
+        match input {
+            Input::PaddleUp(side) if self.keys_down.is_some() => Input::HoldUp(side),
+            Input::PaddleDown(side) if self.keys_down.is_some() => Input::HoldDown(side),
+            input => input
+        }
+    }
+
+    // quick saves while a round is on, quick loads as well once it is over
+    // -> true if the key was one of them
+    fn quick_key(&mut self, key: Key) -> bool {
+        let (on, over) = match self.game.state() {
+            State::Countdown(_) | State::Playing => (true, false),
+            State::GameOver | State::Win => (false, true),
+            _ => (false, false)
+        };
+        match self.key_map.control(key) {
+            Some(Control::QuickSave) if on => self.quick_save(),
+            Some(Control::QuickLoad) if on || over => self.quick_load(),
+            _ => return false
+        }
+        true
+    }
+
+    fn quick_save(&self) {
+        let path = match &self.quick_save_path {
+            Some(path) => path,
+            None => {
+                eprintln!("snake: nowhere to quick save (--quick-save FILE picks a file)");
+                return;
+            }
+        };
+        match save::save(&self.game, path) {
+            Ok(()) => eprintln!("game saved to {}", path.display()),
+            Err(err) => eprintln!("snake: cannot save the game to {}: {}", path.display(), err)
+        }
+    }
+
+    // (not while recording: a replay starts from its seed, not from a saved game)
+    fn quick_load(&mut self) {
+        let path = match &self.quick_save_path {
+            Some(path) => path.clone(),
+            None => {
+                eprintln!("snake: nowhere to quick load from (--quick-save FILE picks a file)");
+                return;
+            }
+        };
+        if self.recording.is_some() {
+            eprintln!("snake: cannot quick load while recording a replay");
+            return;
+        }
+        match save::load(&path) {
+            Ok(game) => {
+                eprintln!("game loaded from {}", path.display());
+                self.resume(game);
+            }
+            Err(err) => eprintln!("snake: cannot load the game from {}: {}", path.display(), err)
+        }
+    }
+
+    // play on from a saved game, after a countdown (or from the end of its round, if it was over)
+    // -> the mode follows the snakes on its board, and the computer players its paddles
+    pub fn resume(&mut self, game: Game) {
+        self.game = game;
+        self.last_tick = self.game.tick();
+        self.asked_tick = None;
+        self.round_ended = self.game.game_over() || self.game.game_win();
+        self.new_rank = None;
+        let snakes = self.game.snakes().count();
+        if snakes != self.choices.mode.snakes() {
+            self.choices.mode = if snakes > 1 { Mode::TwoSnakes } else { Mode::Versus };
+        }
+        self.set_up_players();
+        if !self.round_ended {
+            self.game.set_state(State::Countdown(COUNTDOWN));
+        }
+    }
+
+    // moving through the main menu
+    fn title_key(&mut self, selected: usize, key: Key) {
+        let item = TitleItem::ALL[selected];
+        match menu_input_for_key(key) {
+            Some(MenuInput::Up) => self.game.set_state(State::Title(select(selected, TitleItem::ALL.len(), -1))),
+            Some(MenuInput::Down) => self.game.set_state(State::Title(select(selected, TitleItem::ALL.len(), 1))),
+            Some(MenuInput::Left) => self.choose(item, -1),
+            Some(MenuInput::Right) => self.choose(item, 1),
+            Some(MenuInput::Select) => match item {
+                TitleItem::Start => {
+                    self.set_up_game();
+                    self.set_up_players();
+                    self.game.set_state(State::Countdown(COUNTDOWN));
+                }
+                TitleItem::Mode | TitleItem::Difficulty => self.choose(item, 1),
+                TitleItem::Controls => self.game.set_state(State::Controls { selected: 0, notice: String::new() }),
+                TitleItem::HighScores => self.game.set_state(State::HighScores),
+                TitleItem::Quit => self.game.set_state(State::Quit)
+            },
+            Some(MenuInput::Back) => self.game.set_state(State::Quit),
+            None => ()
+        }
+    }
+
+    // another mode or difficulty
+    // (a difficulty picked here is for every paddle)
+    fn choose(&mut self, item: TitleItem, step: isize) {
+        self.choices.change(item, step);
+        if item == TitleItem::Difficulty {
+            self.paddle_ai.clear();
+        }
+    }
+
+    // moving through the key bindings, Enter waits for a new key
+    fn controls_key(&mut self, selected: usize, key: Key) {
+        let controls = Control::all().count();
+        if key == Key::Backspace {
+            self.key_map = KeyMap::default();
+            self.save_key_map();
+            let notice = "Default keys are back".to_owned();
+            self.game.set_state(State::Controls { selected, notice });
+            return;
+        }
+        match menu_input_for_key(key) {
+            Some(MenuInput::Up) => self.game.set_state(State::Controls {
+                selected: select(selected, controls, -1),
+                notice: String::new()
+            }),
+            Some(MenuInput::Down) => self.game.set_state(State::Controls {
+                selected: select(selected, controls, 1),
+                notice: String::new()
+            }),
+            Some(MenuInput::Select) => self.game.set_state(State::Rebinding(selected)),
+            Some(MenuInput::Back) => self.game.set_state(State::Title(TitleItem::Controls.index())),
+            _ => ()
+        }
+    }
+
+    // the key picked for a control (Esc leaves it as it was)
+    // -> refused if it does something else already
+    fn rebind(&mut self, index: usize, key: Key) {
+        let control = Control::all().nth(index).unwrap();
+        let notice = if key == Key::Escape {
+            String::new()
+        } else {
+            match self.key_map.bind(control, key) {
+                Ok(()) => {
+                    self.save_key_map();
+                    format!("{} is now {}", control, key)
+                }
+                Err(err) => err
+            }
+        };
+        self.game.set_state(State::Controls { selected: index, notice });
+    }
+
+    fn save_key_map(&self) {
+        if let Some(path) = &self.key_map_path {
+            if let Err(err) = self.key_map.save(path) {
+                eprintln!("snake: cannot save key bindings to {}: {}", path.display(), err);
+            }
+        }
+    }
+
+    // moving through the pause menu
+    // (a replay cannot be restarted: its inputs belong to the round being played back)
+    fn menu_key(&mut self, selected: usize, key: Key) {
+        match menu_input_for_key(key) {
+            Some(MenuInput::Up) => self.game.set_state(State::Paused(select(selected, MenuItem::ALL.len(), -1))),
+            Some(MenuInput::Down) => self.game.set_state(State::Paused(select(selected, MenuItem::ALL.len(), 1))),
+            Some(MenuInput::Back) => self.game.set_state(State::Countdown(COUNTDOWN)),
+            Some(MenuInput::Select) => match MenuItem::ALL[selected] {
+                MenuItem::Resume => self.game.set_state(State::Countdown(COUNTDOWN)),
+                MenuItem::Restart if self.playback.is_some() => (),
+                MenuItem::Restart => self.give(Input::Restart),
+                MenuItem::Quit => self.game.set_state(State::Quit)
+            },
+            _ => ()
+        }
+    }
+
+    // an input of the player, recorded on the tick it is given
+    // (or sent to the host of a joined match, or played on the next tick of a rollback match)
+    fn give(&mut self, input: Input) {
+        if let Some(rollback) = &mut self.rollback {
+            rollback.give(input);
+            return;
+        }
+        if let Some(remote) = &mut self.remote {
+            // (a host that is gone shows on the next update)
+            let _ = remote.client.send(input);
+            return;
+        }
+        // (keys the game ignores, e.g. during a countdown, are left out: the inputs of the tick
+        // after a restart are played back once it is playing again)
+        let ignored = !self.game.state().is_playing() && !matches!(input, Input::Restart | Input::Release(_));
+        if let Some(recording) = self.recording.as_mut().filter(|_| !ignored) {
+            recording.record(self.game.tick(), input);
+        }
+        self.game.key_pressed(input);
+    }
+
+    // true once the player asked to leave
+    pub fn quit(&self) -> bool {
+        *self.game.state() == State::Quit
+    }
+
+    // move the game on by the time since the last frame
+    // (or show what the host sent, when playing a joined match)
+    pub fn update(&mut self, delta_time: f64) {
+        if let Some(rollback) = &mut self.rollback {
+            rollback.update(delta_time);
+            if rollback.lost() {
+                eprintln!("snake: the other player left");
+                self.game.set_state(State::Quit);
+            }
+            self.serve();
+        } else if let Some(remote) = &mut self.remote {
+            remote.since_tick += delta_time;
+            match remote.client.poll() {
+                Ok(Some(frame)) => {
+                    // (a tick moves the snakes or the food, a paddle alone does not)
+                    let ticked = |last: &Frame| last.view.snakes != frame.view.snakes || last.view.food != frame.view.food;
+                    if remote.frame.as_ref().is_none_or(ticked) {
+                        remote.since_tick = 0.0;
+                    }
+                    remote.frame = Some(frame);
+                }
+                Ok(None) => (),
+                Err(err) => {
+                    eprintln!("snake: lost the host: {}", err);
+                    self.game.set_state(State::Quit);
+                }
+            }
+        } else {
+            self.serve();
+            self.play(delta_time);
+            self.check_round_end();
+        }
+        if let Some(host) = &mut self.host {
+            let frame = match &self.rollback {
+                Some(rollback) => Frame { motion: rollback.motion(), ..Frame::new(rollback.game()) },
+                None => Frame::new(&self.game)
+            };
+            host.send(&frame);
+        }
+
+        let view = self.view();
+        if let Some(gif) = &mut self.gif {
+            if let Err(err) = gif.capture(view, delta_time, &self.colors) {
+                eprintln!("snake: cannot write {}: {}", gif.path.display(), err);
+                self.gif = None;
+            }
+        }
+    }
+
+    // let remote players in & give their inputs, as if they were pressed here
+    // (the parts the computer plays are kept, and every part of a rollback match)
+    fn serve(&mut self) {
+        let mut host = match self.host.take() {
+            Some(host) => host,
+            None => return
+        };
+        let game = self.rollback.as_ref().map_or(&self.game, Rollback::game);
+        let events = host.poll(game, |role| self.rollback.is_some() || self.computer_plays(role));
+        for event in events {
+            match event {
+                Event::Joined(role) => eprintln!("a player joined for the {}", role),
+                Event::Left(role) => eprintln!("the player of the {} left", role),
+                Event::Watching => eprintln!("a spectator came in ({} watching)", host.spectators()),
+                Event::StoppedWatching => eprintln!("a spectator went away ({} watching)", host.spectators()),
+                Event::Input(input) => self.give(input)
+            }
+        }
+        self.host = Some(host);
+    }
+
+    fn play(&mut self, delta_time: f64) {
+        // feed the recorded inputs on the tick they happened,
+        // and hold the last frame once the replay is over
+        if let Some(playback) = &mut self.playback {
+            // (not while counting down after a restart: the inputs of the next tick are given
+            // right before it is played, as they were in the match)
+            if !matches!(self.game.state(), State::Countdown(_)) {
+                for input in playback.due(self.game.tick()) {
+                    self.game.key_pressed(input);
+                }
+            }
+            if playback.finished(self.game.tick()) {
+                return;
+            }
+        }
+
+        // ahead of every tick (several when the frame was slow): the computer players answer
+        // once per tick, the bots' answers that came in time are played, and the replay gives
+        // the inputs due on it
+        let Session { game, playback, recording, controllers, bots, last_tick, asked_tick, .. } = self;
+        game.update_with(delta_time, |game| {
+            if game.tick() != *last_tick {
+                *last_tick = game.tick();
+                for controller in controllers.iter_mut() {
+                    for input in controller.control(game) {
+                        if let Some(recording) = recording {
+                            recording.record(game.tick(), input);
+                        }
+                        game.key_pressed(input);
+                    }
+                }
+            }
+            // (ticks played one after the other in a slow frame leave the bots no time)
+            if *asked_tick != Some(game.tick()) {
+                *asked_tick = Some(game.tick());
+                bots.iter_mut().for_each(|bot| bot.ask(game));
+            }
+            for bot in bots.iter_mut() {
+                for input in bot.answered() {
+                    if let Some(recording) = recording {
+                        recording.record(game.tick(), input);
+                    }
+                    game.key_pressed(input);
+                }
+            }
+            match playback {
+                Some(playback) => {
+                    for input in playback.due(game.tick()) {
+                        game.key_pressed(input);
+                    }
+                    !playback.finished(game.tick())
+                }
+                None => true
+            }
+        });
+
+        // the bots think about the next tick while the frames go on
+        let waiting = game.state().is_playing() || matches!(game.state(), State::Countdown(_));
+        if waiting && *asked_tick != Some(game.tick()) {
+            *asked_tick = Some(game.tick());
+            bots.iter_mut().for_each(|bot| bot.ask(game));
+        }
+    }
+
+    // ask for initials once a round ends with a score for the table
+    // (only for the player's own rounds: not for replays, with the autopilot, a snake bot, two players or remote players)
+    fn check_round_end(&mut self) {
+        let ended = self.game.game_over() || self.game.game_win();
+        if !ended {
+            self.round_ended = false;
+            return;
+        }
+        if self.round_ended {
+            return;
+        }
+        self.round_ended = true;
+        self.new_rank = None;
+
+        let remote_players = self.host.as_ref().is_some_and(|host| host.roles().next().is_some());
+        let players_round = self.playback.is_none() && !self.computer_plays(Role::Snake) && !remote_players && self.game.snakes().count() == 1;
+        if let Some((_, high_scores)) = &self.high_scores {
+            if players_round && high_scores.qualifies(self.game.score()) {
+                let won = self.game.game_win();
+                self.game.set_state(State::HighScoreEntry { name: String::new(), won });
+            }
+        }
+    }
+
+    // put the round's score into the table & save it
+    fn add_high_score(&mut self, name: &str) {
+        if let Some((path, high_scores)) = &mut self.high_scores {
+            let entry = HighScore::new(name, self.game.score(), self.game.play_time(), self.game.seed());
+            self.new_rank = high_scores.insert(entry);
+            if let Err(err) = high_scores.save(&path) {
+                eprintln!("snake: cannot save high scores to {}: {}", path.display(), err);
+            }
+        }
+    }
+
+    // lines shown with the board: what the screen asks of the player,
+    // and the best scores once a round is over or when asked for
+    pub fn screen_lines(&self) -> Vec<String> {
+        if let Some(rollback) = &self.rollback {
+            let mut lines = rollback_lines(rollback);
+            lines.extend(self.host.as_ref().map(|host| format!("Watching: {}", host.spectators())));
+            return lines;
+        }
+        if let Some(remote) = &self.remote {
+            let status = remote.frame.as_ref().map_or("Waiting for the host", |frame| frame.status.as_str());
+            let part = remote.client.role().map_or("watching".to_owned(), |role| role.to_string());
+            return vec![format!("Online: {}", part), status.to_owned(), "Esc: leave".to_owned()];
+        }
+        let mut lines = self.game.state().lines(&self.choices, &self.key_map);
+        let table = self.game.game_over() || self.game.game_win() || *self.game.state() == State::HighScores;
+        if let (true, Some((_, high_scores))) = (table, &self.high_scores) {
+            lines.push(String::new());
+            lines.push("High scores".to_owned());
+            for (rank, entry) in high_scores.scores.iter().enumerate() {
+                let mark = if self.new_rank == Some(rank) { ">" } else { " " };
+                lines.push(format!("{}{:>2}. {}", mark, rank + 1, entry.line()));
+            }
+        }
+        for bot in &self.bots {
+            if let Some(trouble) = bot.trouble() {
+                lines.push(format!("Bot {} ({}) left out: {}", bot.name(), bot.role(), trouble));
+            }
+        }
+        if let Some(host) = &self.host {
+            let roles: Vec<String> = host.roles().map(|role| role.to_string()).collect();
+            lines.push(String::new());
+            if roles.is_empty() {
+                lines.push("Hosting: nobody joined yet".to_owned());
+            } else {
+                lines.push(format!("Hosting: {}", roles.join(", ")));
+            }
+            if host.spectators() > 0 {
+                lines.push(format!("Watching: {}", host.spectators()));
+            }
+        }
+        lines
+    }
+
+    // what the side panel says about the replay
+    pub fn replay_label(&self) -> &'static str {
+        match &self.playback {
+            Some(playback) if playback.finished(self.game.tick()) => "Replay finished",
+            Some(_) => "Replay",
+            None => ""
+        }
+    }
+
+    // save the recording & the animation, if there are any
+    pub fn finish(self, path: Option<&Path>) {
+        if let Some(gif) = self.gif {
+            let gif_path = gif.path.clone();
+            match gif.finish(&self.colors) {
+                Ok(()) => eprintln!("animation saved to {}", gif_path.display()),
+                Err(err) => eprintln!("snake: cannot write {}: {}", gif_path.display(), err)
+            }
+        }
+        if let (Some(path), Some(mut recording)) = (path, self.recording) {
+            recording.finish(self.game.tick());
+            match recording.save(path) {
+                Ok(()) => eprintln!("replay saved to {}", path.display()),
+                Err(err) => eprintln!("snake: cannot save replay {}: {}", path.display(), err)
+            }
+        }
+    }
+}
+
+// what the side panel says about a rollback match: the part played, and how far ahead of the
+// other peer's inputs this one plays
+fn rollback_lines(rollback: &Rollback) -> Vec<String> {
+    let game = rollback.game();
+    let mut lines = match rollback.role() {
+        None => vec!["Waiting for the other player".to_owned()],
+        Some(role) if rollback.hosting() => vec![
+            format!("Rollback: the other player plays the {}", role),
+            format!("Ahead: {} ticks, played again: {}", rollback.ahead(), rollback.rolled_back())
+        ],
+        Some(role) => vec![
+            format!("Rollback: playing the {}", role),
+            format!("Ahead: {} ticks, played again: {}", rollback.ahead(), rollback.rolled_back())
+        ]
+    };
+    if game.game_over() || game.game_win() {
+        lines.push("Enter: next round".to_owned());
+    }
+    lines.push("Esc: leave".to_owned());
+    lines
+}
+
+// the table from the data directory
+// (a broken file is reported and left alone: scores are not kept this time)
+fn load_high_scores() -> Option<(PathBuf, HighScores)> {
+    let path = HighScores::default_path()?;
+    match HighScores::load(&path) {
+        Ok(high_scores) => Some((path, high_scores)),
+        Err(err) => {
+            eprintln!("snake: cannot read high scores from {}: {}", path.display(), err);
+            None
+        }
+    }
+}
+
+// the key map from the file given on the command line, or from the config directory
+// (a broken file is reported and left alone: the default keys are played with this time)
+fn load_key_map(path: Option<&Path>) -> (Option<PathBuf>, KeyMap) {
+    let path = match path.map(Path::to_owned).or_else(KeyMap::default_path) {
+        Some(path) => path,
+        None => return (None, KeyMap::default())
+    };
+    match KeyMap::load(&path) {
+        Ok(key_map) => (Some(path), key_map),
+        Err(err) => {
+            eprintln!("snake: cannot read key bindings from {}: {}", path.display(), err);
+            (None, KeyMap::default())
+        }
+    }
+}
+
+impl GifCapture {
+    // the view after a frame that lasted some seconds
+    fn capture(&mut self, view: View, delta_time: f64, colors: &Colors) -> io::Result<()> {
+        match &mut self.shown {
+            Some((shown, seconds)) if *shown == view => *seconds += delta_time,
+            _ => {
+                if let Some((shown, seconds)) = self.shown.take() {
+                    self.animation.push(&render(&shown, colors, self.block), seconds)?;
+                }
+                self.shown = Some((view, 0.0));
+            }
+        }
+        Ok(())
+    }
+
+    // write the last frame
+    fn finish(mut self, colors: &Colors) -> io::Result<()> {
+        if let Some((shown, seconds)) = self.shown.take() {
+            self.animation.push(&render(&shown, colors, self.block), seconds.max(1.0))?;
+        }
+        Ok(())
+    }
+}
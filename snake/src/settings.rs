@@ -0,0 +1,368 @@
+// game settings, loaded from a TOML file at startup
+// every key is optional, missing ones keep the values below
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::objects::Side;
+
+// RGB, opacity
+pub type Color = [f32; 4];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub board: Board,
+    pub speed: Speed,
+    pub rules: Rules,
+    pub colors: Colors,
+    // at most one paddle per side, sides without one are walls the food bounces off
+    pub paddles: Vec<PaddleSettings>
+}
+
+// size of the gameboard in blocks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Board {
+    pub width: i32,
+    pub height: i32
+}
+
+// moving speed & response time, in seconds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Speed {
+    pub moving_period: f64,
+    // how long an exported GIF holds the end of a round
+    pub restart_time: f64,
+    // blocks per second a paddle moves while its key is held
+    pub paddle_rate: f64
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Rules {
+    // score the snake wins at
+    pub win_score: i32,
+    // snakes on the board, 1 or 2 (two players racing for the food)
+    pub snakes: usize
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PaddleSettings {
+    pub side: Side,
+    // in blocks
+    #[serde(default = "default_paddle_length")]
+    pub length: i32,
+    // blocks moved per key press
+    #[serde(default = "default_paddle_speed")]
+    pub speed: i32
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Colors {
+    pub background: Color,
+    pub text: Color,
+    pub snake: Color,
+    pub second_snake: Color,
+    pub paddle: Color,
+    pub enemy: Color,
+    pub food: Color,
+    pub border: Color,
+    pub side: Color,
+    pub game_over: Color,
+    pub game_win: Color
+}
+
+// what went wrong while loading the settings
+#[derive(Debug)]
+pub enum SettingsError {
+    // the file could not be read
+    Io(PathBuf, io::Error),
+    // the file is not valid TOML or has unknown keys / wrong types
+    Parse(PathBuf, toml::de::Error),
+    // a value is out of its range
+    Invalid(String)
+}
+
+impl Default for Board {
+    fn default() -> Board {
+        Board {
+            width: 30,
+            height: 30
+        }
+    }
+}
+
+impl Default for Speed {
+    fn default() -> Speed {
+        Speed {
+            moving_period: 0.3,
+            restart_time: 2.0,
+            paddle_rate: 10.0
+        }
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules {
+            win_score: 21,
+            snakes: 1
+        }
+    }
+}
+
+fn default_paddle_length() -> i32 {
+    5
+}
+
+fn default_paddle_speed() -> i32 {
+    1
+}
+
+impl PaddleSettings {
+    // a paddle of the default length & speed
+    pub fn new(side: Side) -> PaddleSettings {
+        PaddleSettings {
+            side,
+            length: default_paddle_length(),
+            speed: default_paddle_speed()
+        }
+    }
+}
+
+// snakes -> green & yellow, food -> blue, enemy -> red, border -> black, gameover -> light red, gamewin -> light blue
+impl Default for Colors {
+    fn default() -> Colors {
+        Colors {
+            background: [0.50, 0.50, 0.50, 1.0],
+            text: [0.00, 0.00, 0.00, 1.0],
+            snake: [0.00, 0.80, 0.00, 1.0],
+            second_snake: [0.90, 0.80, 0.00, 1.0],
+            paddle: [0.00, 0.00, 0.00, 1.0],
+            enemy: [0.80, 0.00, 0.00, 1.0],
+            food: [0.00, 0.00, 0.80, 1.0],
+            border: [0.00, 0.00, 0.00, 1.0],
+            side: [0.00, 0.00, 0.00, 0.5],
+            game_over: [0.90, 0.00, 0.00, 0.5],
+            game_win: [0.00, 0.00, 0.90, 0.5]
+        }
+    }
+}
+
+// one paddle on the left & one on the right
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            board: Board::default(),
+            speed: Speed::default(),
+            rules: Rules::default(),
+            colors: Colors::default(),
+            paddles: vec![PaddleSettings::new(Side::Left), PaddleSettings::new(Side::Right)]
+        }
+    }
+}
+
+impl Settings {
+    // read & validate the settings file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Settings, SettingsError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .map_err(|err| SettingsError::Io(path.to_owned(), err))?;
+        let settings: Settings = toml::from_str(&text)
+            .map_err(|err| SettingsError::Parse(path.to_owned(), err))?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    // settings as TOML text
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).unwrap()
+    }
+
+    // check every value is one the game can play with
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        // food spawns 5 blocks away from the boundaries
+        if self.board.width < 12 || self.board.height < 12 {
+            return Err(invalid(format!(
+                "board must be at least 12x12 blocks (got {}x{})",
+                self.board.width, self.board.height
+            )));
+        }
+        if self.board.width > 200 || self.board.height > 200 {
+            return Err(invalid(format!(
+                "board must be at most 200x200 blocks (got {}x{})",
+                self.board.width, self.board.height
+            )));
+        }
+
+        if self.speed.moving_period.is_nan() || self.speed.moving_period <= 0.0 {
+            return Err(invalid(format!(
+                "speed.moving_period must be more than 0 seconds (got {})",
+                self.speed.moving_period
+            )));
+        }
+        if self.speed.restart_time.is_nan() || self.speed.restart_time < 0.0 {
+            return Err(invalid(format!(
+                "speed.restart_time cannot be negative (got {})",
+                self.speed.restart_time
+            )));
+        }
+
+        if !self.speed.paddle_rate.is_finite() || self.speed.paddle_rate <= 0.0 {
+            return Err(invalid(format!(
+                "speed.paddle_rate must be more than 0 blocks per second (got {})",
+                self.speed.paddle_rate
+            )));
+        }
+
+        if self.rules.win_score < 1 {
+            return Err(invalid(format!(
+                "rules.win_score must be at least 1 (got {})",
+                self.rules.win_score
+            )));
+        }
+        if self.rules.snakes < 1 || self.rules.snakes > 2 {
+            return Err(invalid(format!(
+                "rules.snakes must be 1 or 2 (got {})",
+                self.rules.snakes
+            )));
+        }
+
+        for (index, paddle) in self.paddles.iter().enumerate() {
+            let name = format!("paddles[{}] ({:?})", index, paddle.side).to_lowercase();
+            if self.paddles[..index].iter().any(|other| other.side == paddle.side) {
+                return Err(invalid(format!("{} is the second paddle on that side", name)));
+            }
+            // room along the side, between the corners
+            let room = if paddle.side.is_vertical() {
+                self.board.height - 2
+            } else {
+                self.board.width - 2
+            };
+            if paddle.length < 1 || paddle.length > room {
+                return Err(invalid(format!(
+                    "{}.length must be between 1 and {} (got {})",
+                    name, room, paddle.length
+                )));
+            }
+            if paddle.speed < 1 {
+                return Err(invalid(format!(
+                    "{}.speed must be at least 1 (got {})",
+                    name, paddle.speed
+                )));
+            }
+        }
+
+        for (name, color) in self.colors.named() {
+            if !color.iter().all(|c| (0.0..=1.0).contains(c)) {
+                return Err(invalid(format!(
+                    "colors.{} must have every component between 0.0 and 1.0 (got {:?})",
+                    name, color
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Colors {
+    // color of a player's snake, player one first
+    pub fn snake_color(&self, index: usize) -> Color {
+        if index == 0 {
+            self.snake
+        } else {
+            self.second_snake
+        }
+    }
+
+    // every color with its key in the settings file
+    fn named(&self) -> [(&str, Color); 11] {
+        [
+            ("background", self.background),
+            ("text", self.text),
+            ("snake", self.snake),
+            ("second_snake", self.second_snake),
+            ("paddle", self.paddle),
+            ("enemy", self.enemy),
+            ("food", self.food),
+            ("border", self.border),
+            ("side", self.side),
+            ("game_over", self.game_over),
+            ("game_win", self.game_win)
+        ]
+    }
+}
+
+fn invalid(message: String) -> SettingsError {
+    SettingsError::Invalid(message)
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SettingsError::Io(path, err) => write!(f, "cannot read {}: {}", path.display(), err),
+            SettingsError::Parse(path, err) => write!(f, "{}: {}", path.display(), err),
+            SettingsError::Invalid(message) => write!(f, "invalid settings: {}", message)
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invalid(change: impl FnOnce(&mut Settings)) -> String {
+        let mut settings = Settings::default();
+        change(&mut settings);
+        settings.validate().expect_err("settings should be refused").to_string()
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        assert!(Settings::default().validate().is_ok());
+    }
+
+    #[test]
+    fn toml_round_trip() {
+        let mut settings = Settings::default();
+        settings.board.width = 40;
+        settings.rules.win_score = 5;
+        settings.paddles.push(PaddleSettings::new(Side::Top));
+        let text = settings.to_toml();
+        assert_eq!(toml::from_str::<Settings>(&text).unwrap(), settings);
+    }
+
+    #[test]
+    fn missing_keys_keep_their_defaults() {
+        let settings: Settings = toml::from_str("[board]\nwidth = 40\n").unwrap();
+        assert_eq!(settings.board.width, 40);
+        assert_eq!(settings.board.height, Board::default().height);
+        assert_eq!(settings.speed, Speed::default());
+        assert!(toml::from_str::<Settings>("[board]\ndepth = 3\n").is_err());
+    }
+
+    #[test]
+    fn values_out_of_range_are_refused() {
+        assert!(invalid(|settings| settings.board.width = 11).contains("at least 12x12"));
+        assert!(invalid(|settings| settings.board.height = 201).contains("at most 200x200"));
+        assert!(invalid(|settings| settings.speed.moving_period = 0.0).contains("moving_period"));
+        assert!(invalid(|settings| settings.speed.restart_time = f64::NAN).contains("restart_time"));
+        assert!(invalid(|settings| settings.speed.paddle_rate = f64::NAN).contains("paddle_rate"));
+        assert!(invalid(|settings| settings.rules.snakes = 3).contains("1 or 2"));
+        assert!(invalid(|settings| settings.rules.win_score = 0).contains("win_score"));
+        assert!(invalid(|settings| settings.colors.food[0] = 2.0).contains("colors.food"));
+        assert!(invalid(|settings| settings.paddles[0].length = 29).contains("length"));
+        assert!(invalid(|settings| settings.paddles[1].speed = 0).contains("speed"));
+        assert!(invalid(|settings| settings.paddles.push(PaddleSettings::new(Side::Left))).contains("second paddle"));
+    }
+}
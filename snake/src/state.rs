@@ -0,0 +1,309 @@
+// screens of the game & how the player moves between them
+// (a new Game is already playing, the front ends start a match on the title screen)
+//
+//   Title          --Start-->             Countdown
+//   Title          --Key bindings-->      Controls       --Esc-->          Title
+//   Controls       --Enter-->             Rebinding      --a key / Esc-->  Controls
+//   Title          --High scores-->       HighScores     --Enter / Esc-->  Title
+//   Title          --Quit / Esc-->        Quit
+//   Countdown      --3 seconds-->         Playing
+//   Countdown      --P / Esc-->           Paused
+//   Playing        --P / Esc-->           Paused
+//   Playing        --round ends-->        GameOver / Win, or HighScoreEntry for a good score
+//   Paused         --Resume / Esc-->      Countdown
+//   Paused         --Restart-->           Countdown (new round)
+//   Paused         --Quit-->              Quit
+//   HighScoreEntry --Enter-->             GameOver / Win
+//   GameOver / Win --Enter-->             Countdown (new round)
+//   GameOver / Win --Esc-->               Title (new round)
+use serde::{Deserialize, Serialize};
+
+use crate::keymap::{Control, KeyMap};
+use crate::paddle_ai::Difficulty;
+
+// seconds counted down before a round starts (or goes on after a pause)
+pub const COUNTDOWN: f64 = 3.0;
+
+// entries of the main menu on the title screen
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TitleItem {
+    Start,
+    Mode,
+    Difficulty,
+    Controls,
+    HighScores,
+    Quit
+}
+
+// entries of the pause menu
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MenuItem {
+    Resume,
+    Restart,
+    Quit
+}
+
+// who plays what
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Mode {
+    // people play the snake & every paddle
+    Versus,
+    // the computer plays the paddles
+    Snake,
+    // the computer plays the snake
+    Paddles,
+    // the computer plays everything
+    Watch,
+    // two people play a snake each, the computer plays the paddles
+    TwoSnakes
+}
+
+// what the title screen lets the player pick before a match
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Choices {
+    pub mode: Mode,
+    // of the computer paddles
+    pub difficulty: Difficulty
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum State {
+    // main menu, with the highlighted item
+    Title(usize),
+    // the keys of the game, with the highlighted control
+    // and what went wrong with the last key picked, if anything
+    Controls { selected: usize, notice: String },
+    // waiting for the key of a control
+    Rebinding(usize),
+    // the best scores
+    HighScores,
+    // seconds left before the game moves
+    Countdown(f64),
+    Playing,
+    // pause menu, with the highlighted item
+    Paused(usize),
+    GameOver,
+    Win,
+    // initials typed so far, and whether the round was won
+    HighScoreEntry { name: String, won: bool },
+    // the player asked to leave
+    Quit
+}
+
+// title menu design
+impl TitleItem {
+    pub const ALL: [TitleItem; 6] = [
+        TitleItem::Start,
+        TitleItem::Mode,
+        TitleItem::Difficulty,
+        TitleItem::Controls,
+        TitleItem::HighScores,
+        TitleItem::Quit
+    ];
+
+    // place in the menu
+    pub fn index(&self) -> usize {
+        TitleItem::ALL.iter().position(|item| item == self).unwrap()
+    }
+
+    // its line in the menu, with the current choice if it has one
+    pub fn label(&self, choices: &Choices) -> String {
+        match *self {
+            TitleItem::Start => "Start game".to_owned(),
+            TitleItem::Mode => format!("Mode: < {} >", choices.mode.label()),
+            TitleItem::Difficulty => format!("Difficulty: < {} >", choices.difficulty),
+            TitleItem::Controls => "Key bindings".to_owned(),
+            TitleItem::HighScores => "High scores".to_owned(),
+            TitleItem::Quit => "Quit".to_owned()
+        }
+    }
+}
+
+// menu design
+impl MenuItem {
+    pub const ALL: [MenuItem; 3] = [MenuItem::Resume, MenuItem::Restart, MenuItem::Quit];
+
+    pub fn label(&self) -> &'static str {
+        match *self {
+            MenuItem::Resume => "Resume",
+            MenuItem::Restart => "Restart",
+            MenuItem::Quit => "Quit"
+        }
+    }
+}
+
+// mode design
+impl Mode {
+    pub const ALL: [Mode; 5] = [Mode::Versus, Mode::Snake, Mode::Paddles, Mode::Watch, Mode::TwoSnakes];
+
+    pub fn label(&self) -> &'static str {
+        match *self {
+            Mode::Versus => "snake vs paddles",
+            Mode::Snake => "snake vs computer",
+            Mode::Paddles => "paddles vs computer",
+            Mode::Watch => "computer vs computer",
+            Mode::TwoSnakes => "snake vs snake"
+        }
+    }
+
+    // the computer steers the snake
+    pub fn autopilot(&self) -> bool {
+        *self == Mode::Paddles || *self == Mode::Watch
+    }
+
+    // the computer plays the paddles
+    pub fn paddle_ai(&self) -> bool {
+        *self == Mode::Snake || *self == Mode::Watch || *self == Mode::TwoSnakes
+    }
+
+    // snakes on the board
+    pub fn snakes(&self) -> usize {
+        if *self == Mode::TwoSnakes {
+            2
+        } else {
+            1
+        }
+    }
+}
+
+// choices design
+impl Choices {
+    // the choice of an item, one step to the left (-1) or to the right (1)
+    pub fn change(&mut self, item: TitleItem, step: isize) {
+        match item {
+            TitleItem::Mode => self.mode = cycle(&Mode::ALL, self.mode, step),
+            TitleItem::Difficulty => self.difficulty = cycle(&Difficulty::ALL, self.difficulty, step),
+            _ => ()
+        }
+    }
+}
+
+// the entry some steps away from the current one, going round at either end
+fn cycle<T: Copy + PartialEq>(all: &[T], current: T, step: isize) -> T {
+    let index = all.iter().position(|entry| *entry == current).unwrap_or(0) as isize;
+    all[(index + step).rem_euclid(all.len() as isize) as usize]
+}
+
+// the highlighted item one step up (-1) or down (1) a menu of some items, going round
+pub fn select(selected: usize, items: usize, step: isize) -> usize {
+    (selected as isize + step).rem_euclid(items as isize) as usize
+}
+
+// state design
+impl State {
+    // the game moves only while playing
+    pub fn is_playing(&self) -> bool {
+        *self == State::Playing
+    }
+
+    // screens of the main menu, shown over the board
+    pub fn is_menu(&self) -> bool {
+        matches!(self, State::Title(_) | State::Controls { .. } | State::Rebinding(_) | State::HighScores)
+    }
+
+    // screen shown once a round is over
+    pub fn round_end(won: bool) -> State {
+        if won {
+            State::Win
+        } else {
+            State::GameOver
+        }
+    }
+
+    // what the screen tells the player, line by line
+    pub fn lines(&self, choices: &Choices, key_map: &KeyMap) -> Vec<String> {
+        match self {
+            State::Title(selected) => {
+                let mut lines = vec!["Snake Pong".to_owned(), String::new()];
+                for (index, item) in TitleItem::ALL.iter().enumerate() {
+                    let mark = if index == *selected { ">" } else { " " };
+                    lines.push(format!("{} {}", mark, item.label(choices)));
+                }
+                lines.push(String::new());
+                lines.push("Up / Down, Left / Right & Enter".to_owned());
+                lines
+            }
+            State::Controls { selected, notice } => {
+                let mut lines = vec!["Key bindings".to_owned(), String::new()];
+                for (index, control) in Control::all().enumerate() {
+                    let mark = if index == *selected { ">" } else { " " };
+                    let keys: Vec<String> = key_map.keys(control).iter().map(|key| key.to_string()).collect();
+                    lines.push(format!("{} {:<20} {}", mark, control, keys.join(" / ")));
+                }
+                lines.push(String::new());
+                lines.push(notice.clone());
+                lines.push("Enter: change, Backspace: defaults, Esc: back".to_owned());
+                lines
+            }
+            State::Rebinding(index) => {
+                let control = Control::all().nth(*index).unwrap();
+                vec![format!("Press a key for {}", control), "Esc: cancel".to_owned()]
+            }
+            State::HighScores => vec!["Enter / Esc: back".to_owned()],
+            State::Countdown(left) => vec![format!("Starting in {}", left.ceil().max(1.0))],
+            State::Playing => {
+                let mut lines = vec!["P / Esc: pause".to_owned()];
+                let quick = (key_map.keys(Control::QuickSave).first(), key_map.keys(Control::QuickLoad).first());
+                if let (Some(save), Some(load)) = quick {
+                    lines.push(format!("{} / {}: quick save / load", save, load));
+                }
+                lines
+            }
+            State::Paused(selected) => {
+                let mut lines = vec!["Paused".to_owned(), String::new()];
+                for (index, item) in MenuItem::ALL.iter().enumerate() {
+                    let mark = if index == *selected { ">" } else { " " };
+                    lines.push(format!("{} {}", mark, item.label()));
+                }
+                lines.push(String::new());
+                lines.push("Up / Down & Enter".to_owned());
+                lines
+            }
+            State::GameOver | State::Win => vec![
+                "Enter: play again".to_owned(),
+                "Esc: title".to_owned()
+            ],
+            State::HighScoreEntry { name, .. } => vec![
+                format!("New high score! Initials: {}_", name),
+                "Enter: save".to_owned()
+            ],
+            State::Quit => Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn menus_go_round() {
+        assert_eq!(select(0, TitleItem::ALL.len(), -1), TitleItem::ALL.len() - 1);
+        assert_eq!(select(TitleItem::ALL.len() - 1, TitleItem::ALL.len(), 1), 0);
+        assert_eq!(select(1, MenuItem::ALL.len(), 1), 2);
+    }
+
+    #[test]
+    fn left_and_right_change_the_choices() {
+        let mut choices = Choices { mode: Mode::Versus, difficulty: Difficulty::Normal };
+        choices.change(TitleItem::Mode, -1);
+        assert_eq!(choices.mode, *Mode::ALL.last().unwrap());
+        choices.change(TitleItem::Mode, 1);
+        assert_eq!(choices.mode, Mode::Versus);
+        choices.change(TitleItem::Difficulty, 1);
+        assert_eq!(choices.difficulty, Difficulty::Hard);
+        // (other items have nothing to pick)
+        choices.change(TitleItem::Start, 1);
+        assert_eq!(choices, Choices { mode: Mode::Versus, difficulty: Difficulty::Hard });
+    }
+
+    #[test]
+    fn the_title_shows_the_choices_and_the_selected_item() {
+        let choices = Choices { mode: Mode::Watch, difficulty: Difficulty::Easy };
+        let mode = TitleItem::ALL.iter().position(|item| *item == TitleItem::Mode).unwrap();
+        let lines = State::Title(mode).lines(&choices, &KeyMap::default());
+        assert!(lines.contains(&format!("> Mode: < {} >", Mode::Watch.label())));
+        assert!(lines.contains(&"  Difficulty: < easy >".to_owned()));
+        assert!(lines.contains(&"  Start game".to_owned()));
+    }
+}
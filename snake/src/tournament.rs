@@ -0,0 +1,730 @@
+// tournaments between agents (bots, or the built-in computer players) without any window,
+// to compare them by their results & Elo ratings
+// -> snake agents race each other with two snakes on the board (the computer plays the paddles),
+//    paddle agents guard the left & the right side against each other (the autopilot plays the
+//    snake): the snake that wins, or the paddle that does not let the food through, wins the game
+//    (a round ending any other way, or running out of ticks, is a draw)
+// every pairing plays the same seeds, each twice with the agents' places swapped, so neither
+// gets the better corner or side
+//
+// round robin: every agent plays every other agent of its kind once
+// swiss: a number of rounds, each pairing agents with as many points as each other that have
+//        not met yet (one of an odd number sits the round out, a bye, and gets the points of a
+//        whole pairing won; agents meet again only when there is no other way to pair them)
+use std::cmp::Ordering;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use std::sync::mpsc::channel;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use crate::autopilot::Autopilot;
+use crate::bot::Bot;
+use crate::controller::Controller;
+use crate::game::Game;
+use crate::net::Role;
+use crate::objects::Side;
+use crate::paddle_ai::{Difficulty, PaddleAi};
+use crate::replay::Replay;
+use crate::settings::Settings;
+
+// commands of the built-in agents
+const AUTOPILOT: &str = "builtin:autopilot";
+const BUILTIN: &str = "builtin:";
+
+// rating every agent starts from, and how far one game moves it
+pub const INITIAL_ELO: f64 = 1500.0;
+const ELO_K: f64 = 16.0;
+
+// computer paddles of the snake games
+const HOUSE_PADDLES: Difficulty = Difficulty::Hard;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Kind {
+    Snake,
+    Paddle
+}
+
+// a player of a tournament: a bot's command, or "builtin:autopilot" for snakes and
+// "builtin:easy", "builtin:normal" or "builtin:hard" for paddles
+#[derive(Debug, Clone, PartialEq)]
+pub struct Agent {
+    pub name: String,
+    pub kind: Kind,
+    pub command: String
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Format {
+    RoundRobin,
+    // with its number of rounds
+    Swiss(u32)
+}
+
+#[derive(Debug, Clone)]
+pub struct TournamentSettings {
+    // board, speeds, paddles & win score of every game
+    // (snake games have two snakes; paddle games need a left & a right paddle)
+    pub game: Settings,
+    pub format: Format,
+    // seeds every pairing plays, from this one on
+    pub games: u32,
+    pub seed: u64,
+    // games played at once
+    pub jobs: usize,
+    // a game still going after this many ticks is a draw
+    pub max_ticks: u64,
+    // time a bot has to answer every tick
+    pub bot_time: Duration
+}
+
+// one game to play: two agents of a kind, first & second place, and the seed
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Pairing {
+    pub round: u32,
+    // place in the tournament's games
+    pub number: usize,
+    pub kind: Kind,
+    // indices into the agents
+    // (the first plays the first snake or the left paddle)
+    pub first: usize,
+    pub second: usize,
+    pub seed: u64
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Outcome {
+    First,
+    Second,
+    Draw
+}
+
+// what came out of a game
+#[derive(Debug, Clone)]
+pub struct GameResult {
+    pub pairing: Pairing,
+    pub outcome: Outcome,
+    pub scores: Vec<i32>,
+    pub ticks: u64,
+    // every input of the game, to watch it again
+    pub replay: Replay,
+    // what went wrong with the agents, if anything (a bot that did not start loses the game)
+    pub trouble: Vec<String>
+}
+
+// an agent's results so far
+#[derive(Debug, Clone, PartialEq)]
+pub struct Standing {
+    pub agent: usize,
+    pub games: u32,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    // 1 per win, 1/2 per draw, and the points of a whole pairing won (2 per seed) for every bye
+    pub points: f64,
+    pub elo: f64,
+    // swiss rounds sat out
+    pub byes: u32,
+    // agents it was paired with
+    met: Vec<usize>
+}
+
+pub struct Tournament {
+    agents: Vec<Agent>,
+    settings: TournamentSettings,
+    standings: Vec<Standing>,
+    // every game played, in the order of the pairings (without their replays)
+    results: Vec<GameResult>
+}
+
+// kind design
+impl Kind {
+    // the parts an agent of the kind plays, in the first & second place
+    fn roles(&self) -> [Role; 2] {
+        match self {
+            Kind::Snake => [Role::Snake, Role::SecondSnake],
+            Kind::Paddle => [Role::Paddle(Side::Left), Role::Paddle(Side::Right)]
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Kind::Snake => f.write_str("snake"),
+            Kind::Paddle => f.write_str("paddle")
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    // "round-robin", or "swiss" with a number of rounds, e.g. "swiss:5"
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s.split_once(':') {
+            None if s == "round-robin" => Ok(Format::RoundRobin),
+            Some(("swiss", rounds)) => match rounds.parse::<u32>() {
+                Ok(rounds) if rounds > 0 => Ok(Format::Swiss(rounds)),
+                _ => Err(format!("invalid number of swiss rounds '{}'", rounds))
+            },
+            _ => Err(format!("unknown format '{}' (round-robin, or swiss:ROUNDS e.g. swiss:5)", s))
+        }
+    }
+}
+
+// agent design
+impl Agent {
+    // "NAME=COMMAND" for a kind, e.g. "v2=python3 bots/follow_food.py" or "hard=builtin:hard"
+    pub fn parse(kind: Kind, value: &str) -> Result<Agent, String> {
+        let (name, command) = match value.split_once('=') {
+            Some((name, command)) if !name.trim().is_empty() && !command.trim().is_empty() => {
+                (name.trim(), command.trim())
+            }
+            _ => return Err(format!("invalid agent '{}' (NAME=COMMAND, e.g. v2=\"python3 bot.py\")", value))
+        };
+        let agent = Agent { name: name.to_owned(), kind, command: command.to_owned() };
+        if let Some(builtin) = command.strip_prefix(BUILTIN) {
+            let known = match kind {
+                Kind::Snake => command == AUTOPILOT,
+                Kind::Paddle => builtin.parse::<Difficulty>().is_ok()
+            };
+            if !known {
+                let builtins = match kind {
+                    Kind::Snake => AUTOPILOT.to_owned(),
+                    Kind::Paddle => "builtin:easy, builtin:normal or builtin:hard".to_owned()
+                };
+                return Err(format!("no built-in {} agent '{}' ({})", kind, command, builtins));
+            }
+        }
+        Ok(agent)
+    }
+
+    // the player of a part of a game, started for that game alone
+    fn player(&self, role: Role, game: &Game, seed: u64, bot_time: Duration) -> io::Result<Box<dyn Controller>> {
+        let builtin = match self.command.strip_prefix(BUILTIN) {
+            Some(builtin) => builtin,
+            None => return Ok(Box::new(Bot::spawn(&self.command, role, game, bot_time)?))
+        };
+        match role {
+            Role::Snake => Ok(Box::new(Autopilot::new())),
+            Role::SecondSnake => Ok(Box::new(Autopilot::steering(1))),
+            Role::Paddle(side) => {
+                let difficulty = builtin.parse().map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+                Ok(Box::new(PaddleAi::new(side, difficulty, seed)))
+            }
+        }
+    }
+}
+
+// play one game of a tournament, from the first tick to the end of the round
+pub fn play(pairing: &Pairing, agents: &[Agent], settings: &TournamentSettings) -> GameResult {
+    let mut game_settings = settings.game.clone();
+    game_settings.rules.snakes = match pairing.kind {
+        Kind::Snake => 2,
+        Kind::Paddle => 1
+    };
+    let mut game = Game::new(&game_settings, pairing.seed);
+    let mut replay = Replay::new(pairing.seed, &game_settings);
+    let mut trouble = Vec::new();
+
+    // the two agents, then the computer for the parts they do not play
+    // (an agent that cannot start loses, unless both cannot)
+    let mut players: Vec<Box<dyn Controller>> = Vec::new();
+    let mut failed = [false; 2];
+    let roles = pairing.kind.roles();
+    for (place, (index, role)) in [pairing.first, pairing.second].iter().zip(roles.iter()).enumerate() {
+        let agent = &agents[*index];
+        let seed = pairing.seed.wrapping_add(place as u64 + 1);
+        match agent.player(*role, &game, seed, settings.bot_time) {
+            Ok(player) => players.push(player),
+            Err(err) => {
+                trouble.push(format!("{} did not start: {}", agent.name, err));
+                failed[place] = true;
+            }
+        }
+    }
+    match pairing.kind {
+        Kind::Snake => {
+            for (index, paddle) in game_settings.paddles.iter().enumerate() {
+                let seed = pairing.seed.wrapping_add(index as u64 + 3);
+                players.push(Box::new(PaddleAi::new(paddle.side, HOUSE_PADDLES, seed)));
+            }
+        }
+        Kind::Paddle => players.push(Box::new(Autopilot::new()))
+    }
+
+    if failed[0] || failed[1] {
+        let outcome = match failed {
+            [true, false] => Outcome::Second,
+            [false, true] => Outcome::First,
+            _ => Outcome::Draw
+        };
+        return GameResult { pairing: *pairing, outcome, scores: game.scores(), ticks: 0, replay, trouble };
+    }
+
+    // the players answer once per tick, and every answer goes into the replay
+    while !(game.game_over() || game.game_win()) && game.tick() < settings.max_ticks {
+        for player in &mut players {
+            for input in player.control(&game) {
+                replay.record(game.tick(), input);
+                game.key_pressed(input);
+            }
+        }
+        game.step();
+    }
+    replay.finish(game.tick());
+
+    let outcome = match pairing.kind {
+        Kind::Snake => match game.winner() {
+            Some(0) if game.game_win() => Outcome::First,
+            Some(1) if game.game_win() => Outcome::Second,
+            _ => Outcome::Draw
+        },
+        Kind::Paddle => match game.missed_by() {
+            Some(Side::Left) => Outcome::Second,
+            Some(Side::Right) => Outcome::First,
+            _ => Outcome::Draw
+        }
+    };
+    GameResult { pairing: *pairing, outcome, scores: game.scores(), ticks: game.tick(), replay, trouble }
+}
+
+// elo rating after a game against an opponent: score 1 for a win, 1/2 for a draw, 0 for a loss
+pub fn elo_after(rating: f64, opponent: f64, score: f64) -> f64 {
+    let expected = 1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0));
+    rating + ELO_K * (score - expected)
+}
+
+// tournament design
+impl Tournament {
+    // a tournament between agents
+    // -> refused if a kind has a single agent (nobody to play), or none has two
+    pub fn new(agents: Vec<Agent>, settings: TournamentSettings) -> Result<Tournament, String> {
+        for kind in [Kind::Snake, Kind::Paddle].iter() {
+            let count = agents.iter().filter(|agent| agent.kind == *kind).count();
+            if count == 1 {
+                return Err(format!("a single {} agent has nobody to play", kind));
+            }
+            for (index, agent) in agents.iter().enumerate().filter(|(_, agent)| agent.kind == *kind) {
+                if agents[..index].iter().any(|other| other.kind == *kind && other.name == agent.name) {
+                    return Err(format!("two {} agents are called {}", kind, agent.name));
+                }
+            }
+        }
+        if agents.len() < 2 {
+            return Err("a tournament needs at least two snake or two paddle agents".to_owned());
+        }
+        let has_paddles = [Side::Left, Side::Right].iter()
+            .all(|side| settings.game.paddles.iter().any(|paddle| paddle.side == *side));
+        if agents.iter().any(|agent| agent.kind == Kind::Paddle) && !has_paddles {
+            return Err("paddle agents play on a board with a left & a right paddle".to_owned());
+        }
+        settings.game.validate().map_err(|err| err.to_string())?;
+
+        let standings = (0..agents.len())
+            .map(|agent| Standing {
+                agent,
+                games: 0,
+                wins: 0,
+                draws: 0,
+                losses: 0,
+                points: 0.0,
+                elo: INITIAL_ELO,
+                byes: 0,
+                met: Vec::new()
+            })
+            .collect();
+        Ok(Tournament { agents, settings, standings, results: Vec::new() })
+    }
+
+    pub fn agents(&self) -> &[Agent] {
+        &self.agents
+    }
+
+    // every game played so far, in order (their replays are handed out as they are played)
+    pub fn results(&self) -> &[GameResult] {
+        &self.results
+    }
+
+    // play every round, jobs games at a time
+    // -> each game is handed to on_game as soon as it is over, replay included
+    //    (the ratings only move once a round is over, in the order of its games, so that they do
+    //    not depend on which game ends first)
+    // -> an error if a round cannot be paired
+    pub fn run<F: FnMut(&GameResult)>(&mut self, mut on_game: F) -> Result<(), String> {
+        let rounds = match self.settings.format {
+            Format::RoundRobin => 1,
+            Format::Swiss(rounds) => rounds
+        };
+        for round in 1..=rounds {
+            let pairings = self.pairings(round)?;
+            let mut results = self.play_all(&pairings, &mut on_game);
+            results.sort_by_key(|result| result.pairing.number);
+            for mut result in results {
+                self.count(&result);
+                // (replays are big, they were handed out already)
+                result.replay.inputs = Vec::new();
+                self.results.push(result);
+            }
+        }
+        Ok(())
+    }
+
+    // the games of a round: every pairing of agents of a kind, on every seed, both ways round
+    fn pairings(&mut self, round: u32) -> Result<Vec<Pairing>, String> {
+        let mut matches = Vec::new();
+        for kind in [Kind::Snake, Kind::Paddle].iter() {
+            let agents: Vec<usize> = (0..self.agents.len()).filter(|index| self.agents[*index].kind == *kind).collect();
+            let pairs = match self.settings.format {
+                Format::RoundRobin => round_robin(&agents),
+                Format::Swiss(_) => self.swiss(agents)?
+            };
+            matches.extend(pairs.into_iter().map(|pair| (*kind, pair)));
+        }
+
+        let mut pairings = Vec::new();
+        let first_number = self.results.len();
+        for (kind, (a, b)) in matches {
+            for game in 0..self.settings.games {
+                let seed = self.settings.seed.wrapping_add(game as u64);
+                for (first, second) in [(a, b), (b, a)].iter() {
+                    let number = first_number + pairings.len();
+                    pairings.push(Pairing { round, number, kind, first: *first, second: *second, seed });
+                }
+            }
+        }
+        Ok(pairings)
+    }
+
+    // pairs of a swiss round: the best placed agent with the next best placed it has not met yet,
+    // swapping partners with an earlier pair when the ones left over have met
+    // (if the number is odd, one of those that sat out the fewest rounds sits out, the lowest placed
+    // that leaves a pairing without rematches; rematches only come when there is none)
+    fn swiss(&mut self, mut agents: Vec<usize>) -> Result<Vec<(usize, usize)>, String> {
+        agents.sort_by(|a, b| self.compare(*a, *b));
+        let mut sitting_out: Vec<Option<usize>> = vec![None];
+        if agents.len() % 2 == 1 {
+            let fewest = agents.iter().map(|agent| self.standings[*agent].byes).min().unwrap_or(0);
+            sitting_out = agents.iter().rev()
+                .filter(|agent| self.standings[**agent].byes == fewest)
+                .map(|agent| Some(*agent))
+                .collect();
+        }
+
+        for rematches in [false, true].iter() {
+            for sits_out in &sitting_out {
+                let playing: Vec<usize> = agents.iter().copied().filter(|agent| Some(*agent) != *sits_out).collect();
+                let may_meet = |a: usize, b: usize| *rematches || !self.standings[a].met.contains(&b);
+                if let Some(pairs) = pair_up(&playing, &may_meet) {
+                    if let Some(agent) = sits_out {
+                        let standing = &mut self.standings[*agent];
+                        standing.byes += 1;
+                        standing.points += 2.0 * self.settings.games as f64;
+                    }
+                    return Ok(pairs);
+                }
+            }
+        }
+        Err(format!("cannot pair the {} agents of a swiss round", agents.len()))
+    }
+
+    // play the games of a round at once, jobs at a time
+    fn play_all<F: FnMut(&GameResult)>(&self, pairings: &[Pairing], on_game: &mut F) -> Vec<GameResult> {
+        let queue = Mutex::new(pairings.iter());
+        let (sender, receiver) = channel();
+        let (agents, settings) = (&self.agents, &self.settings);
+        thread::scope(|scope| {
+            for _ in 0..settings.jobs.max(1) {
+                let (queue, sender) = (&queue, sender.clone());
+                scope.spawn(move || loop {
+                    let pairing = match queue.lock().unwrap().next() {
+                        Some(pairing) => *pairing,
+                        None => break
+                    };
+                    if sender.send(play(&pairing, agents, settings)).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(sender);
+            receiver.iter().inspect(|result| on_game(result)).collect()
+        })
+    }
+
+    // the results & ratings after a game
+    fn count(&mut self, result: &GameResult) {
+        let (first, second) = (result.pairing.first, result.pairing.second);
+        let score = match result.outcome {
+            Outcome::First => 1.0,
+            Outcome::Second => 0.0,
+            Outcome::Draw => 0.5
+        };
+        let (first_elo, second_elo) = (self.standings[first].elo, self.standings[second].elo);
+        for (agent, opponent, score, elo, opponent_elo) in
+            [(first, second, score, first_elo, second_elo), (second, first, 1.0 - score, second_elo, first_elo)].iter()
+        {
+            let standing = &mut self.standings[*agent];
+            standing.games += 1;
+            standing.points += score;
+            match score.partial_cmp(&0.5) {
+                Some(Ordering::Greater) => standing.wins += 1,
+                Some(Ordering::Less) => standing.losses += 1,
+                _ => standing.draws += 1
+            }
+            standing.elo = elo_after(*elo, *opponent_elo, *score);
+            if !standing.met.contains(opponent) {
+                standing.met.push(*opponent);
+            }
+        }
+    }
+
+    // more points first, then the better rating, then by name
+    fn compare(&self, a: usize, b: usize) -> Ordering {
+        let (a, b) = (&self.standings[a], &self.standings[b]);
+        b.points.partial_cmp(&a.points).unwrap_or(Ordering::Equal)
+            .then(b.elo.partial_cmp(&a.elo).unwrap_or(Ordering::Equal))
+            .then_with(|| self.agents[a.agent].name.cmp(&self.agents[b.agent].name))
+    }
+
+    // the standings of a kind, best first
+    pub fn standings(&self, kind: Kind) -> Vec<&Standing> {
+        let mut agents: Vec<usize> = (0..self.agents.len()).filter(|index| self.agents[*index].kind == kind).collect();
+        agents.sort_by(|a, b| self.compare(*a, *b));
+        agents.into_iter().map(|agent| &self.standings[agent]).collect()
+    }
+
+    // the standings of every kind as a table
+    pub fn standings_text(&self) -> String {
+        let mut text = String::new();
+        for kind in [Kind::Snake, Kind::Paddle].iter() {
+            let standings = self.standings(*kind);
+            if standings.is_empty() {
+                continue;
+            }
+            let width = standings.iter().map(|standing| self.agents[standing.agent].name.len()).max().unwrap_or(0).max(5);
+            text.push_str(&format!("{} agents\n", kind));
+            text.push_str(&format!(
+                "{:>4}  {:<width$}  {:>5}  {:>4}  {:>4}  {:>4}  {:>4}  {:>6}  {:>6}\n",
+                "rank", "agent", "games", "won", "draw", "lost", "byes", "points", "elo", width = width
+            ));
+            for (rank, standing) in standings.iter().enumerate() {
+                text.push_str(&format!(
+                    "{:>4}  {:<width$}  {:>5}  {:>4}  {:>4}  {:>4}  {:>4}  {:>6.1}  {:>6.0}\n",
+                    rank + 1, self.agents[standing.agent].name, standing.games, standing.wins, standing.draws,
+                    standing.losses, standing.byes, standing.points, standing.elo, width = width
+                ));
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    // every game as a CSV line, with the name of its replay file
+    pub fn games_csv(&self) -> String {
+        let mut text = String::from("round,game,kind,first,second,seed,winner,scores,ticks,replay\n");
+        for result in &self.results {
+            let pairing = &result.pairing;
+            let (first, second) = (&self.agents[pairing.first].name, &self.agents[pairing.second].name);
+            let winner = match result.outcome {
+                Outcome::First => first.as_str(),
+                Outcome::Second => second.as_str(),
+                Outcome::Draw => ""
+            };
+            let scores: Vec<String> = result.scores.iter().map(|score| score.to_string()).collect();
+            text.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{}\n",
+                pairing.round, pairing.number + 1, pairing.kind, csv(first), csv(second), pairing.seed, csv(winner),
+                scores.join(" "), result.ticks, replay_name(&self.agents, result)
+            ));
+        }
+        text
+    }
+}
+
+// file name of a game's replay, e.g. "r1-g0007-snake-v2-vs-v1-s42.replay"
+pub fn replay_name(agents: &[Agent], result: &GameResult) -> String {
+    let pairing = &result.pairing;
+    let name = |agent: usize| -> String {
+        agents[agent].name.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect()
+    };
+    format!(
+        "r{}-g{:04}-{}-{}-vs-{}-s{}.replay",
+        pairing.round, pairing.number + 1, pairing.kind, name(pairing.first), name(pairing.second), pairing.seed
+    )
+}
+
+// pairs of an even number of agents, best placed first, each with the next best placed it may
+// meet; two left over that may not meet swap partners with an earlier pair, if one lets them
+// -> none if that is not enough to pair them all
+// (a pass over the agents & one over the pairs for each left over: no search through every pairing)
+fn pair_up<F: Fn(usize, usize) -> bool>(agents: &[usize], may_meet: &F) -> Option<Vec<(usize, usize)>> {
+    let mut pairs = Vec::new();
+    let mut stuck = Vec::new();
+    let mut left = agents.to_vec();
+    while !left.is_empty() {
+        let first = left.remove(0);
+        match left.iter().position(|opponent| may_meet(first, *opponent)) {
+            Some(index) => pairs.push((first, left.remove(index))),
+            None => stuck.push(first)
+        }
+    }
+
+    for two in stuck.chunks(2) {
+        let (a, b) = match two {
+            [a, b] => (*a, *b),
+            _ => return None
+        };
+        if may_meet(a, b) {
+            pairs.push((a, b));
+            continue;
+        }
+        let swap = pairs.iter().enumerate().find_map(|(index, (c, d))| {
+            if may_meet(*c, a) && may_meet(*d, b) {
+                Some((index, (*c, a), (*d, b)))
+            } else if may_meet(*c, b) && may_meet(*d, a) {
+                Some((index, (*c, b), (*d, a)))
+            } else {
+                None
+            }
+        });
+        let (index, first, second) = swap?;
+        pairs[index] = first;
+        pairs.push(second);
+    }
+    Some(pairs)
+}
+
+// every pair of agents once
+fn round_robin(agents: &[usize]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    for (index, a) in agents.iter().enumerate() {
+        for b in &agents[index + 1..] {
+            pairs.push((*a, *b));
+        }
+    }
+    pairs
+}
+
+// a CSV field, quoted if it has to be
+fn csv(field: &str) -> String {
+    if field.contains(',') || field.contains('"') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(format: Format) -> TournamentSettings {
+        TournamentSettings {
+            game: Settings::default(),
+            format,
+            games: 1,
+            seed: 7,
+            jobs: 2,
+            max_ticks: 300,
+            bot_time: Duration::from_millis(50)
+        }
+    }
+
+    fn snakes(count: usize) -> Vec<Agent> {
+        (0..count)
+            .map(|index| Agent::parse(Kind::Snake, &format!("s{}=builtin:autopilot", index)).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn elo_moves_by_the_surprise_of_the_result() {
+        assert_eq!(elo_after(1500.0, 1500.0, 1.0), 1508.0);
+        assert_eq!(elo_after(1500.0, 1500.0, 0.5), 1500.0);
+        assert_eq!(elo_after(1500.0, 1500.0, 0.0), 1492.0);
+        // the favourite gains less for a win than the outsider would, and what one wins the other loses
+        let (strong, weak) = (1700.0, 1400.0);
+        assert!(elo_after(strong, weak, 1.0) - strong < elo_after(weak, strong, 1.0) - weak);
+        let total = elo_after(strong, weak, 0.0) + elo_after(weak, strong, 1.0);
+        assert!((total - (strong + weak)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn round_robin_pairs_every_agent_once() {
+        let pairs = round_robin(&[0, 1, 2, 3]);
+        assert_eq!(pairs, vec![(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)]);
+    }
+
+    #[test]
+    fn swiss_never_pairs_agents_again_while_it_can_help_it() {
+        let mut tournament = Tournament::new(snakes(5), settings(Format::Swiss(5))).unwrap();
+        let mut pairs = Vec::new();
+        for _ in 0..5 {
+            let round = tournament.swiss((0..5).collect()).unwrap();
+            assert_eq!(round.len(), 2);
+            for (a, b) in round {
+                assert!(!tournament.standings[a].met.contains(&b), "{} & {} met again", a, b);
+                tournament.standings[a].met.push(b);
+                tournament.standings[b].met.push(a);
+                pairs.push((a.min(b), a.max(b)));
+            }
+        }
+        // five rounds of five agents: every pair once, and every agent sits out once
+        pairs.sort();
+        assert_eq!(pairs, round_robin(&[0, 1, 2, 3, 4]));
+        assert!(tournament.standings.iter().all(|standing| standing.byes == 1));
+        assert!(tournament.standings.iter().all(|standing| standing.points == 2.0));
+
+        // once every pair has met, agents meet again rather than not play
+        assert_eq!(tournament.swiss((0..5).collect()).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn swiss_goes_back_on_a_pair_that_leaves_the_others_stuck() {
+        let mut tournament = Tournament::new(snakes(4), settings(Format::Swiss(3))).unwrap();
+        // 2 & 3 and 1 & 3 met: pairing 0 with 1, or with 2, leaves two that met, only 0-3 & 1-2 work
+        for (a, b) in [(2, 3), (1, 3)].iter() {
+            tournament.standings[*a].met.push(*b);
+            tournament.standings[*b].met.push(*a);
+        }
+        let mut round = tournament.swiss((0..4).collect()).unwrap();
+        round.iter_mut().for_each(|pair| *pair = (pair.0.min(pair.1), pair.0.max(pair.1)));
+        round.sort();
+        assert_eq!(round, vec![(0, 3), (1, 2)]);
+    }
+
+    #[test]
+    fn byes_show_in_the_standings_and_account_for_the_points() {
+        let mut tournament = Tournament::new(snakes(3), settings(Format::Swiss(3))).unwrap();
+        let mut games = 0;
+        tournament.run(|_| games += 1).unwrap();
+        // three rounds of one pairing, on one seed both ways round
+        assert_eq!(games, 6);
+        let games_per_pairing = 2.0 * tournament.settings.games as f64;
+        for standing in &tournament.standings {
+            assert_eq!(standing.byes, 1);
+            assert_eq!(standing.games, standing.wins + standing.draws + standing.losses);
+            let points = standing.wins as f64 + standing.draws as f64 / 2.0 + standing.byes as f64 * games_per_pairing;
+            assert_eq!(standing.points, points);
+        }
+        assert!(tournament.standings_text().lines().nth(1).unwrap().contains("byes"));
+    }
+
+    #[test]
+    fn pairing_many_agents_takes_one_pass_and_never_panics() {
+        let agents: Vec<usize> = (0..1000).collect();
+        let may_meet = |a: usize, b: usize| !(a + b).is_multiple_of(7) && a % 10 != b % 10;
+        let pairs = pair_up(&agents, &may_meet).unwrap();
+        let mut paired: Vec<usize> = pairs.iter().flat_map(|(a, b)| vec![*a, *b]).collect();
+        paired.sort();
+        assert_eq!(paired, agents);
+        assert!(pairs.iter().all(|(a, b)| may_meet(*a, *b)));
+
+        // (nobody may meet anybody: no pairing, rather than a panic)
+        assert_eq!(pair_up(&[0, 1, 2, 3], &|_, _| false), None);
+    }
+}
@@ -0,0 +1,265 @@
+// the terminal front end, for playing over SSH or without OpenGL
+// every block is two character cells wide so that it looks square
+use std::io::{self, Stdout, Write};
+use std::time::{Duration, Instant};
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+    PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags
+};
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+
+use snake::keys::Key;
+use snake::settings::{self, Colors};
+use snake::View;
+
+use crate::session::Session;
+
+// time between frames when no key is pressed
+const FRAME: Duration = Duration::from_millis(15);
+
+// puts the terminal back the way it was, even on a panic
+struct Terminal {
+    out: Stdout,
+    // the terminal reports keys going up (most only report them going down)
+    key_releases: bool
+}
+
+impl Terminal {
+    fn open() -> io::Result<Terminal> {
+        let mut out = io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(out, EnterAlternateScreen, Hide, Clear(ClearType::All))?;
+        let key_releases = terminal::supports_keyboard_enhancement().unwrap_or(false);
+        if key_releases {
+            execute!(out, PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES))?;
+        }
+        Ok(Terminal { out, key_releases })
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        if self.key_releases {
+            let _ = execute!(self.out, PopKeyboardEnhancementFlags);
+        }
+        let _ = execute!(self.out, ResetColor, Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+// terminal keys -> keys of the game
+fn game_key(code: KeyCode) -> Option<Key> {
+    match code {
+        KeyCode::Up => Some(Key::Up),
+        KeyCode::Down => Some(Key::Down),
+        KeyCode::Left => Some(Key::Left),
+        KeyCode::Right => Some(Key::Right),
+        KeyCode::Enter => Some(Key::Enter),
+        KeyCode::Backspace => Some(Key::Backspace),
+        KeyCode::Esc => Some(Key::Escape),
+        KeyCode::Char(c) if c.is_ascii_alphanumeric() => Some(Key::Char(c.to_ascii_lowercase())),
+        _ => None
+    }
+}
+
+// play the match in the terminal until the player quits (or presses Ctrl-C)
+pub fn run(session: &mut Session, colors: &Colors) -> io::Result<()> {
+    let mut terminal = Terminal::open()?;
+    // held paddle keys keep moving the paddles where the terminal tells when they go up,
+    // elsewhere every key press (or repeat) moves them once
+    if terminal.key_releases {
+        session.track_key_releases();
+    }
+    let mut last_frame = Instant::now();
+    // last thing that happened outside the game, shown on the side panel
+    let mut notice = String::new();
+    // what is on the screen, to redraw only when something changed
+    let mut shown: Option<(View, &str, String, Vec<String>)> = None;
+
+    loop {
+        // key inputs
+        if event::poll(FRAME)? {
+            match event::read()? {
+                Event::Key(KeyEvent { code, kind: KeyEventKind::Release, .. }) => {
+                    if let Some(key) = game_key(code) {
+                        session.key_released(key);
+                    }
+                }
+                Event::Key(KeyEvent { code, modifiers, .. }) => {
+                    let ctrl_c = code == KeyCode::Char('c') && modifiers.contains(KeyModifiers::CONTROL);
+                    if ctrl_c {
+                        return Ok(());
+                    }
+                    if code == KeyCode::F(12) {
+                        notice = match session.screenshot() {
+                            Ok(path) => format!("Saved {}", path.display()),
+                            Err(err) => format!("Screenshot failed: {}", err)
+                        };
+                    }
+                    if let Some(key) = game_key(code) {
+                        session.key_pressed(key);
+                    }
+                }
+                Event::Resize(..) => {
+                    queue!(terminal.out, ResetColor, Clear(ClearType::All))?;
+                    shown = None;
+                }
+                _ => ()
+            }
+        }
+
+        // update game status
+        let now = Instant::now();
+        session.update(now.duration_since(last_frame).as_secs_f64());
+        last_frame = now;
+        if session.quit() {
+            return Ok(());
+        }
+
+        let view = session.view();
+        // menus, initials prompt & best scores
+        let frame = (view, session.replay_label(), notice.clone(), session.screen_lines());
+        if shown.as_ref() != Some(&frame) {
+            let (view, replay_label, notice, screen_lines) = &frame;
+            draw(&mut terminal.out, view, replay_label, notice, screen_lines, colors)?;
+            shown = Some(frame);
+        }
+    }
+}
+
+// draw the board with the side panel to the right
+fn draw(out: &mut Stdout, view: &View, replay_label: &str, notice: &str, screen_lines: &[String],
+        colors: &Colors) -> io::Result<()> {
+    let (columns, rows) = terminal::size()?;
+    if (columns as i32) < view.width * 2 || (rows as i32) < view.height {
+        queue!(
+            out,
+            ResetColor,
+            Clear(ClearType::All),
+            MoveTo(0, 0),
+            Print(format!("make the terminal at least {}x{}", view.width * 2, view.height))
+        )?;
+        return out.flush();
+    }
+
+    // color of every block, back to front as in the window
+    let background = colors.background;
+    let mut cells = vec![background; (view.width * view.height) as usize];
+    let mut paint = |x: i32, y: i32, color: settings::Color| {
+        if x >= 0 && y >= 0 && x < view.width && y < view.height {
+            let cell = &mut cells[(y * view.width + x) as usize];
+            *cell = blend(*cell, color);
+        }
+    };
+
+    for (index, body) in view.snakes.iter().enumerate() {
+        for block in body {
+            paint(block.x, block.y, colors.snake_color(index));
+        }
+    }
+    for (_, body) in &view.paddles {
+        for block in body {
+            paint(block.x, block.y, colors.paddle);
+        }
+    }
+    for block in &view.enemies {
+        paint(block.x, block.y, colors.enemy);
+    }
+    if let Some(food) = view.food {
+        paint(food.x, food.y, colors.food);
+    }
+
+    // boundaries
+    for x in 0..view.width {
+        paint(x, 0, colors.border);
+        paint(x, view.height - 1, colors.border);
+    }
+    for y in 0..view.height {
+        paint(0, y, colors.side);
+        paint(view.width - 1, y, colors.side);
+    }
+
+    // game over & game win screens
+    for y in 0..view.height {
+        for x in 0..view.width {
+            if view.game_over {
+                paint(x, y, colors.game_over);
+            }
+            if view.game_win {
+                paint(x, y, colors.game_win);
+            }
+        }
+    }
+
+    for y in 0..view.height {
+        queue!(out, MoveTo(0, y as u16))?;
+        for x in 0..view.width {
+            let color = cells[(y * view.width + x) as usize];
+            queue!(out, SetBackgroundColor(to_color(color)), Print("  "))?;
+        }
+    }
+
+    // side panel: score, seed & replay
+    let panel = (view.width * 2 + 4) as u16;
+    queue!(
+        out,
+        SetBackgroundColor(to_color(background)),
+        SetForegroundColor(to_color(colors.text)),
+        MoveTo(panel, 1),
+        Clear(ClearType::UntilNewLine),
+        Print(format!("Score: {}", view.score_text())),
+        MoveTo(panel, 2),
+        Clear(ClearType::UntilNewLine),
+        Print(format!("Seed: {}", view.seed)),
+        MoveTo(panel, 3),
+        Clear(ClearType::UntilNewLine),
+        Print(replay_label),
+        MoveTo(panel, 5),
+        Clear(ClearType::UntilNewLine),
+        Print("F12 saves a screenshot, Ctrl-C quits"),
+        MoveTo(panel, 7),
+        Clear(ClearType::UntilNewLine),
+        Print(notice)
+    )?;
+
+    // menus, initials prompt & best scores, below the rest of the panel
+    for row in 9..rows {
+        queue!(out, MoveTo(panel, row), Clear(ClearType::UntilNewLine))?;
+        if let Some(line) = screen_lines.get((row - 9) as usize) {
+            queue!(out, Print(line))?;
+        }
+    }
+
+    // game end statements, over the middle of the board
+    if let Some(statement) = view.statement() {
+        let x = (view.width - statement.len() as i32 / 2).max(0) as u16;
+        queue!(out, MoveTo(x, (view.height / 2) as u16), Print(statement))?;
+    }
+
+    queue!(out, ResetColor)?;
+    out.flush()
+}
+
+// a color drawn over another one, by its opacity
+fn blend(under: settings::Color, over: settings::Color) -> settings::Color {
+    let alpha = over[3];
+    [
+        over[0] * alpha + under[0] * (1.0 - alpha),
+        over[1] * alpha + under[1] * (1.0 - alpha),
+        over[2] * alpha + under[2] * (1.0 - alpha),
+        1.0
+    ]
+}
+
+fn to_color(color: settings::Color) -> Color {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    Color::Rgb {
+        r: channel(color[0]),
+        g: channel(color[1]),
+        b: channel(color[2])
+    }
+}
@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+
+use crate::objects::{Block, Side};
+
+// read-only snapshot of a game, everything a front end needs to draw one frame
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct View {
+    pub width: i32,
+    pub height: i32,
+
+    // every snake, player one first, head first
+    pub snakes: Vec<Vec<Block>>,
+
+    // side each paddle guards & its blocks, first block first
+    pub paddles: Vec<(Side, Vec<Block>)>,
+
+    pub enemies: Vec<Block>,
+    pub food: Option<Block>,
+
+    // every player's score, player one first
+    pub scores: Vec<i32>,
+    // player who won the round, if it was won
+    pub winner: Option<usize>,
+    pub seed: u64,
+    pub game_over: bool,
+    pub game_win: bool
+}
+
+// where the moving parts of a game were before its last tick, and the share of the moving
+// period gone by since (0 = just ticked, 1 = about to tick)
+// -> drawing them in between the two positions makes the motion smooth
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Motion {
+    // every snake, player one first, head first
+    pub snakes: Vec<Vec<Block>>,
+    pub food: Option<Block>,
+    pub progress: f64
+}
+
+// view design
+impl View {
+    // the scores as the side panel shows them, e.g. "7" or "7 - 4" with two players
+    pub fn score_text(&self) -> String {
+        let scores: Vec<String> = self.scores.iter().map(|score| score.to_string()).collect();
+        scores.join(" - ")
+    }
+
+    // what the board says once a round is over, if it is
+    // (with two players, who won)
+    pub fn statement(&self) -> Option<String> {
+        if self.game_over {
+            Some("GAME OVER".to_owned())
+        } else if self.game_win && self.snakes.len() > 1 {
+            Some(format!("PLAYER {} WINS", self.winner.unwrap_or(0) + 1))
+        } else if self.game_win {
+            Some("WIN!".to_owned())
+        } else {
+            None
+        }
+    }
+}
+
+// motion design
+impl Motion {
+    // every block of a player's snake in between where it was & where it is, head first
+    // (a block the snake just grew stays where it is)
+    pub fn snake(&self, view: &View, player: usize) -> Vec<(f64, f64)> {
+        let before = self.snakes.get(player);
+        view.snakes[player].iter()
+            .enumerate()
+            .map(|(index, block)| match before.and_then(|before| before.get(index)) {
+                Some(before) => self.between(before, block),
+                None => (block.x as f64, block.y as f64)
+            })
+            .collect()
+    }
+
+    // the food in between where it was & where it is
+    // (new food shows up where it is)
+    pub fn food(&self, view: &View) -> Option<(f64, f64)> {
+        let food = view.food?;
+        match self.food {
+            Some(before) if (before.x - food.x).abs() <= 1 && (before.y - food.y).abs() <= 1 => {
+                Some(self.between(&before, &food))
+            }
+            _ => Some((food.x as f64, food.y as f64))
+        }
+    }
+
+    fn between(&self, before: &Block, now: &Block) -> (f64, f64) {
+        let t = self.progress;
+        (
+            before.x as f64 + (now.x - before.x) as f64 * t,
+            before.y as f64 + (now.y - before.y) as f64 * t
+        )
+    }
+}
@@ -0,0 +1,176 @@
+// the piston window front end
+use std::process;
+
+use piston_window::*;
+
+use snake::keys;
+use snake::settings::Colors;
+
+use crate::draw::{draw_game, draw_rectangle, to_coord, to_coord_u32};
+use crate::session::Session;
+
+// piston keys -> keys of the game
+fn game_key(key: Key) -> Option<keys::Key> {
+    match key {
+        Key::Up => Some(keys::Key::Up),
+        Key::Down => Some(keys::Key::Down),
+        Key::Left => Some(keys::Key::Left),
+        Key::Right => Some(keys::Key::Right),
+        Key::Return => Some(keys::Key::Enter),
+        Key::Backspace => Some(keys::Key::Backspace),
+        Key::Escape => Some(keys::Key::Escape),
+        // letters & digits have their ASCII code
+        _ => match key.code() {
+            code @ 0x30..=0x39 | code @ 0x61..=0x7a => Some(keys::Key::Char(code as u8 as char)),
+            _ => None
+        }
+    }
+}
+
+// play the match in a window until it is closed or the player quits
+pub fn run(session: &mut Session, colors: &Colors) {
+    // size of the gameboard
+    let (width, height) = (session.game.width(), session.game.height());
+
+    // open up the gameboard with width & height
+    let mut window: PistonWindow =
+        WindowSettings::new("Snake", [to_coord_u32(width), to_coord_u32(height)])
+            .fullscreen(true)
+            // (Esc pauses, the menus quit)
+            .exit_on_esc(false)
+            .build()
+            .unwrap_or_else(|err| {
+                eprintln!("snake: cannot open a window: {} (--tui plays in the terminal)", err);
+                process::exit(1);
+            });
+
+    // load font
+    let assets = find_folder::Search::ParentsThenKids(3, 3)
+        .for_folder("assets").unwrap();
+    let font = &assets.join("FiraMono-Bold.ttf");
+    let mut glyphs = window.load_font(font).unwrap();
+
+    // the window tells when keys go up, so held paddle keys keep moving the paddles
+    session.track_key_releases();
+
+    // game loop
+    while let Some(e) = window.next() {
+        // key inputs
+        // (F12 saves a screenshot)
+        if let Some(Button::Keyboard(key)) = e.press_args() {
+            if key == Key::F12 {
+                match session.screenshot() {
+                    Ok(path) => eprintln!("screenshot saved to {}", path.display()),
+                    Err(err) => eprintln!("snake: cannot save screenshot: {}", err)
+                }
+            }
+            if let Some(key) = game_key(key) {
+                session.key_pressed(key);
+            }
+        }
+        if let Some(Button::Keyboard(key)) = e.release_args() {
+            if let Some(key) = game_key(key) {
+                session.key_released(key);
+            }
+        }
+
+        // everything drawn this frame
+        let view = session.view();
+        let motion = session.motion();
+
+        // get score & seed and make them strings
+        let scoreboard = format!("Score: {}", view.score_text());
+        let seedboard = format!("Seed: {}", view.seed);
+        let replayboard = session.replay_label();
+        // game over & game win statements
+        let statement = view.statement();
+        // menus, initials prompt & best scores
+        // (the main menu screens go over the board, in a larger font)
+        let screen_lines = session.screen_lines();
+        let menu = session.game.state().is_menu();
+
+        // draw game components & texts
+        window.draw_2d(&e, |c, g, device| {
+            clear(colors.background, g);
+            draw_game(&view, &motion, colors, &c, g);
+
+            // texts follow the size of the board
+            // -> side panel to the right, statements over the middle
+            let (board_x, board_y) = (to_coord(view.width), to_coord(view.height));
+            let transform = c.transform.trans(board_x + 150.0, board_y * 0.4);
+            let seed_transform = c.transform.trans(board_x + 150.0, board_y * 0.4 + 30.0);
+            let replay_transform = c.transform.trans(board_x + 150.0, board_y * 0.4 + 60.0);
+            let statement_transform = c.transform.trans(board_x * 0.6, board_y * 0.53);
+
+            // score text
+            text::Text::new_color(colors.text, 20).draw(
+                &scoreboard,
+                &mut glyphs,
+                &c.draw_state,
+                transform, g
+            ).unwrap();
+
+            // seed text
+            text::Text::new_color(colors.text, 14).draw(
+                &seedboard,
+                &mut glyphs,
+                &c.draw_state,
+                seed_transform, g
+            ).unwrap();
+
+            // replay text
+            text::Text::new_color(colors.text, 14).draw(
+                replayboard,
+                &mut glyphs,
+                &c.draw_state,
+                replay_transform, g
+            ).unwrap();
+
+            // screen texts, under the side panel
+            if !menu {
+                for (index, line) in screen_lines.iter().enumerate() {
+                    let line_transform = c.transform.trans(board_x + 150.0, board_y * 0.4 + 110.0 + 20.0 * index as f64);
+                    text::Text::new_color(colors.text, 14).draw(
+                        line,
+                        &mut glyphs,
+                        &c.draw_state,
+                        line_transform, g
+                    ).unwrap();
+                }
+            }
+
+            // main menu texts, over the shaded board
+            if menu {
+                draw_rectangle(colors.side, 0, 0, view.width, view.height, &c, g);
+                for (index, line) in screen_lines.iter().enumerate() {
+                    let line_transform = c.transform.trans(board_x * 0.1, board_y * 0.2 + 34.0 * index as f64);
+                    text::Text::new_color(colors.text, 24).draw(
+                        line,
+                        &mut glyphs,
+                        &c.draw_state,
+                        line_transform, g
+                    ).unwrap();
+                }
+            }
+
+            // game over & game win statement text
+            if let Some(statement) = &statement {
+                text::Text::new_color(colors.text, 70).draw(
+                    statement,
+                    &mut glyphs,
+                    &c.draw_state,
+                    statement_transform, g
+                ).unwrap();
+            }
+
+            // flush so that the string buffer can be passed to GPU completely
+            glyphs.factory.encoder.flush(device);
+        });
+
+        // update game status
+        e.update(|arg| session.update(arg.dt));
+        if session.quit() {
+            break;
+        }
+    }
+}